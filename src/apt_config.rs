@@ -0,0 +1,192 @@
+use std::fs;
+use std::path::Path;
+
+use crate::constants::*;
+
+#[derive(Default)]
+pub struct AptConfig {
+    pub keep_proxy: bool,
+    pub conf_snippets: Vec<String>,
+    pub repositories: Vec<String>,
+    pub keyrings: Vec<String>,
+}
+
+impl AptConfig {
+    pub fn describe(
+        &self,
+        proxy: Option<&str>
+    ) -> Result<String, std::io::Error> {
+        // Descreve tudo que esta configuração grava no novo sistema
+        let mut description = String::new();
+
+        if self.keep_proxy {
+            if let Some(proxy) = proxy {
+                description += format!("proxy {}\n", proxy).as_str();
+            }
+        }
+
+        for repository in &self.repositories {
+            description += format!("repository {}\n", repository).as_str();
+        }
+
+        for filepath in self.conf_snippets.iter().chain(self.keyrings.iter()) {
+            let contents = read_file(filepath)?;
+            description += format!("file {} {}\n", filepath, String::from_utf8_lossy(&contents)).as_str();
+        }
+
+        Ok(description)
+    }
+}
+
+pub fn configure_target_apt(
+    target_root: &str,
+    apt_config: &AptConfig,
+    proxy: Option<&str>
+) -> Result<(), std::io::Error> {
+    // Copia os trechos de configuração para /etc/apt/apt.conf.d
+    let conf_dir = format!("{}/etc/apt/apt.conf.d", target_root);
+
+    for filepath in &apt_config.conf_snippets {
+        copy_into_directory(filepath, &conf_dir)?;
+    }
+
+    // Mantém o proxy no novo sistema, se solicitado
+    if apt_config.keep_proxy {
+        if let Some(proxy) = proxy {
+            if let Err(_) = fs::create_dir_all(&conf_dir) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("Falha ao criar o diretório {}!", conf_dir)
+                ));
+            }
+
+            let filepath = format!("{}/{}", conf_dir, APT_PROXY_CONF);
+
+            if let Err(_) = fs::write(filepath, format!("Acquire::http::Proxy \"{}\";\n", proxy)) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("Falha ao criar o arquivo /etc/apt/apt.conf.d/{}!", APT_PROXY_CONF)
+                ));
+            }
+        }
+    }
+
+    // Copia os chaveiros dos repositórios extras para /etc/apt/keyrings
+    let keyrings_dir = format!("{}/etc/apt/keyrings", target_root);
+
+    for filepath in &apt_config.keyrings {
+        copy_into_directory(filepath, &keyrings_dir)?;
+    }
+
+    // Cria o arquivo /etc/apt/sources.list.d/<lista> com os repositórios extras
+    if !apt_config.repositories.is_empty() {
+        let mut sources_list = String::new();
+
+        for repository in &apt_config.repositories {
+            sources_list += repository;
+            sources_list += "\n";
+        }
+
+        let filepath = format!("{}/etc/apt/sources.list.d/{}", target_root, EXTRA_SOURCES_LIST);
+
+        if let Err(_) = fs::write(filepath, sources_list) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao criar o arquivo /etc/apt/sources.list.d/{}!", EXTRA_SOURCES_LIST)
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn copy_into_directory(
+    filepath: &str,
+    directory: &str
+) -> Result<(), std::io::Error> {
+    // Copia um arquivo para um diretório do novo sistema, mantendo o nome
+    let filename = match Path::new(filepath).file_name() {
+        Some(filename) => filename,
+        None => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Caminho inválido: {}!", filepath)
+            ));
+        }
+    };
+
+    if let Err(_) = fs::create_dir_all(directory) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao criar o diretório {}!", directory)
+        ));
+    }
+
+    if let Err(_) = fs::copy(filepath, Path::new(directory).join(filename)) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao copiar o arquivo {}!", filepath)
+        ));
+    }
+
+    Ok(())
+}
+
+fn read_file(filepath: &str) -> Result<Vec<u8>, std::io::Error> {
+    match fs::read(filepath) {
+        Ok(contents) => Ok(contents),
+        Err(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao ler o arquivo {}!", filepath)
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROXY: &str = "http://proxy.local:3142";
+
+    fn target_root(name: &str) -> String {
+        let target_root = std::env::temp_dir().join(format!("installer-apt-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&target_root);
+        fs::create_dir_all(&target_root).unwrap();
+        target_root.to_str().unwrap().to_string()
+    }
+
+    fn proxy_conf(target_root: &str) -> String {
+        format!("{}/etc/apt/apt.conf.d/{}", target_root, APT_PROXY_CONF)
+    }
+
+    #[test]
+    fn drops_proxy_from_target_by_default() {
+        let target_root = target_root("drop-proxy");
+        let apt_config = AptConfig::default();
+
+        configure_target_apt(&target_root, &apt_config, Some(PROXY)).unwrap();
+
+        assert!(!Path::new(&proxy_conf(&target_root)).exists());
+        assert_eq!(apt_config.describe(Some(PROXY)).unwrap(), "");
+
+        fs::remove_dir_all(&target_root).unwrap();
+    }
+
+    #[test]
+    fn keeps_proxy_in_target_when_requested() {
+        let target_root = target_root("keep-proxy");
+        let apt_config = AptConfig {
+            keep_proxy: true,
+            ..Default::default()
+        };
+
+        configure_target_apt(&target_root, &apt_config, Some(PROXY)).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(proxy_conf(&target_root)).unwrap(),
+            format!("Acquire::http::Proxy \"{}\";\n", PROXY)
+        );
+
+        fs::remove_dir_all(&target_root).unwrap();
+    }
+}
\ No newline at end of file
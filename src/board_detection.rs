@@ -0,0 +1,104 @@
+use std::fs;
+use std::path::Path;
+
+use crate::fdt::*;
+
+const DEVICE_TREE_DIR: &str = "/proc/device-tree";
+
+pub struct RunningBoard {
+    pub model: Option<String>,
+    pub compatible: Vec<String>,
+}
+
+pub struct DtbMatch {
+    pub dtb_file: String,
+    pub exact: bool,
+}
+
+pub fn read_running_board() -> Option<RunningBoard> {
+    // Lê o modelo e as strings compatible do aparelho em execução
+    let compatible = fs::read(format!("{}/compatible", DEVICE_TREE_DIR)).ok()?;
+    let model = fs::read(format!("{}/model", DEVICE_TREE_DIR))
+        .ok()
+        .and_then(|model| string_list(&model).into_iter().next());
+
+    Some(RunningBoard {
+        model,
+        compatible: string_list(&compatible),
+    })
+}
+
+pub fn find_matching_dtb(
+    dtb_dir: &str,
+    running: &RunningBoard
+) -> Result<Option<DtbMatch>, std::io::Error> {
+    // Compara o aparelho em execução com todos os DTBs do kernel. As strings
+    // compatible vão da mais específica para a mais genérica, então vence o
+    // DTB que contém a string mais específica, desempatando pelo modelo
+    let mut dtb_files: Vec<(String, DeviceTree)> = Vec::new();
+    collect_device_trees(Path::new(dtb_dir), "", &mut dtb_files)?;
+    dtb_files.sort_by(|first, second| first.0.cmp(&second.0));
+
+    let mut best: Option<(usize, bool, String)> = None;
+
+    for (dtb_file, device_tree) in dtb_files {
+        let rank = running.compatible
+            .iter()
+            .position(|compatible| device_tree.compatible.contains(compatible));
+
+        let rank = match rank {
+            Some(rank) => rank,
+            None => continue,
+        };
+        let same_model = running.model.is_some() && device_tree.model == running.model;
+
+        let better = match &best {
+            Some((best_rank, best_model, _)) => rank < *best_rank || (rank == *best_rank && same_model && !best_model),
+            None => true,
+        };
+
+        if better {
+            best = Some((rank, same_model, dtb_file));
+        }
+    }
+
+    // Só a string compatible do próprio aparelho, ou o mesmo modelo,
+    // identifica a placa; as demais identificam apenas o SoC
+    Ok(best.map(|(rank, same_model, dtb_file)| DtbMatch {
+        dtb_file,
+        exact: rank == 0 || same_model,
+    }))
+}
+
+fn collect_device_trees(
+    dir: &Path,
+    prefix: &str,
+    dtb_files: &mut Vec<(String, DeviceTree)>
+) -> Result<(), std::io::Error> {
+    // Percorre o diretório de DTBs, incluindo os subdiretórios de fabricante
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao ler o diretório {}!", dir.display())
+            ));
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+
+        if path.is_dir() {
+            collect_device_trees(&path, &format!("{}/", name), dtb_files)?;
+        } else if name.ends_with(".dtb") {
+            // DTBs ilegíveis são ignorados, pois não podem ser escolhidos
+            if let Ok(device_tree) = read_device_tree(&path.to_string_lossy()) {
+                dtb_files.push((name, device_tree));
+            }
+        }
+    }
+
+    Ok(())
+}
\ No newline at end of file
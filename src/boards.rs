@@ -0,0 +1,323 @@
+use std::fs;
+
+use crate::constants::*;
+use crate::package_sets::*;
+use crate::soc_profiles::*;
+
+// Base de aparelhos embutida no executável
+const EMBEDDED_BOARDS: &str = include_str!("boards.conf");
+
+pub struct Board {
+    pub name: String,
+    pub model: String,
+    pub soc: &'static SocProfile,
+    pub dtb_file: String,
+    pub kernel_config: Vec<String>,
+    pub wifi_firmware: Vec<String>,
+    pub leds: Vec<(String, String)>,
+    pub ir_keymap: Option<String>,
+}
+
+impl Board {
+    pub fn packages(&self) -> Vec<String> {
+        // Pacotes necessários para os periféricos do aparelho
+        let mut packages = self.wifi_firmware.clone();
+
+        if self.ir_keymap.is_some() {
+            packages.push("ir-keytable".to_string());
+        }
+
+        packages
+    }
+}
+
+pub fn load_boards(
+    boards_files: &[String]
+) -> Result<Vec<Board>, std::io::Error> {
+    // Carrega a base embutida e depois os arquivos locais, cujas definições
+    // substituem as embutidas com o mesmo nome
+    let mut boards = parse_boards(EMBEDDED_BOARDS, "base embutida")?;
+
+    for filepath in boards_files {
+        let contents = match fs::read_to_string(filepath) {
+            Ok(contents) => contents,
+            Err(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("Falha ao ler o arquivo de aparelhos {}!", filepath)
+                ));
+            }
+        };
+
+        for board in parse_boards(&contents, filepath)? {
+            boards.retain(|existing| existing.name != board.name);
+            boards.push(board);
+        }
+    }
+
+    Ok(boards)
+}
+
+pub fn find_board(
+    name: &str,
+    boards_files: &[String]
+) -> Result<Board, std::io::Error> {
+    // Os nomes não diferenciam maiúsculas, como os perfis de SoC
+    let name = name.to_lowercase();
+    let boards = load_boards(boards_files)?;
+    let names: Vec<String> = boards.iter().map(|board| board.name.clone()).collect();
+
+    match boards.into_iter().find(|board| board.name == name) {
+        Some(board) => Ok(board),
+        None => Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Aparelho desconhecido: {}! Aparelhos disponíveis: {}.", name, names.join(", "))
+        )),
+    }
+}
+
+fn parse_boards(
+    contents: &str,
+    source: &str
+) -> Result<Vec<Board>, std::io::Error> {
+    // Interpreta seções [<nome>] seguidas de linhas <chave> = <valor>
+    let mut boards: Vec<Board> = Vec::new();
+    let mut entries: Vec<(String, String)> = Vec::new();
+    let mut name: Option<String> = None;
+
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(section) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            if let Some(name) = name.take() {
+                boards.push(build_board(&name, &entries, source)?);
+            }
+
+            name = Some(section.trim().to_lowercase());
+            entries.clear();
+            continue;
+        }
+
+        match line.split_once('=') {
+            Some((key, value)) if name.is_some() && !key.trim().is_empty() => {
+                entries.push((key.trim().to_string(), value.trim().to_string()));
+            },
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("Linha {} inválida em {}!", number + 1, source)
+                ));
+            }
+        }
+    }
+
+    if let Some(name) = name {
+        boards.push(build_board(&name, &entries, source)?);
+    }
+
+    Ok(boards)
+}
+
+fn build_board(
+    name: &str,
+    entries: &[(String, String)],
+    source: &str
+) -> Result<Board, std::io::Error> {
+    let mut model: Option<String> = None;
+    let mut soc: Option<&'static SocProfile> = None;
+    let mut dtb_file: Option<String> = None;
+    let mut kernel_config: Vec<String> = Vec::new();
+    let mut wifi_firmware: Vec<String> = Vec::new();
+    let mut leds: Vec<(String, String)> = Vec::new();
+    let mut ir_keymap: Option<String> = None;
+
+    for (key, value) in entries {
+        match key.as_str() {
+            "model" => model = Some(value.clone()),
+            "soc" => soc = Some(find_soc_profile(value)?),
+            "dtb" => dtb_file = Some(value.clone()),
+            "kernel-config" => {
+                kernel_config = split_list(value)
+                    .iter()
+                    .map(|option| option.trim_start_matches("CONFIG_").to_string())
+                    .collect();
+            },
+            "wifi-firmware" => wifi_firmware = split_list(value),
+            "leds" => {
+                for led in split_list(value) {
+                    match led.rsplit_once(':') {
+                        Some((led, trigger)) if !led.is_empty() && !trigger.is_empty() => {
+                            leds.push((led.to_string(), trigger.to_string()));
+                        },
+                        _ => {
+                            return Err(std::io::Error::new(
+                                std::io::ErrorKind::Other,
+                                format!("LED inválido no aparelho {} ({}): {}!", name, source, led)
+                            ));
+                        }
+                    }
+                }
+            },
+            "ir-keymap" => ir_keymap = Some(value.clone()),
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("Chave desconhecida no aparelho {} ({}): {}!", name, source, key)
+                ));
+            }
+        }
+    }
+
+    match (soc, dtb_file) {
+        (Some(soc), Some(dtb_file)) => Ok(Board {
+            name: name.to_string(),
+            model: model.unwrap_or_else(|| name.to_string()),
+            soc,
+            dtb_file,
+            kernel_config,
+            wifi_firmware,
+            leds,
+            ir_keymap,
+        }),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("O aparelho {} ({}) precisa definir soc e dtb!", name, source)
+        )),
+    }
+}
+
+pub fn configure_board(
+    board: &Board
+) -> Result<(), std::io::Error> {
+    // Define os gatilhos dos LEDs a cada boot
+    if !board.leds.is_empty() {
+        let mut tmpfiles = String::new();
+
+        for (led, trigger) in &board.leds {
+            tmpfiles += format!("w /sys/class/leds/{}/trigger - - - - {}\n", led, trigger).as_str();
+        }
+
+        let tmpfiles_dir = format!("{}/etc/tmpfiles.d", ROOT_MOUNT_POINT);
+
+        if fs::create_dir_all(tmpfiles_dir.as_str()).is_err()
+            || fs::write(format!("{}/{}", tmpfiles_dir, BOARD_LEDS_CONF), tmpfiles).is_err()
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao criar o arquivo /etc/tmpfiles.d/{}!", BOARD_LEDS_CONF)
+            ));
+        }
+    }
+
+    // Carrega o mapa de teclas do controle remoto para qualquer receptor
+    if let Some(ir_keymap) = &board.ir_keymap {
+        let rc_maps = format!("* * /lib/udev/rc_keymaps/{}.toml\n", ir_keymap);
+
+        if let Err(_) = fs::write(format!("{}/etc/rc_maps.cfg", ROOT_MOUNT_POINT), rc_maps) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "Falha ao criar o arquivo /etc/rc_maps.cfg!"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOARDS: &str = "\
+# Comentário
+[Beelink-X2]
+model = Beelink X2
+soc = h3
+dtb = sun8i-h3-beelink-x2.dtb
+kernel-config = CONFIG_LEDS_GPIO, IR_SUNXI
+wifi-firmware = firmware-realtek firmware-misc-nonfree
+leds = beelink-x2:blue:pwr:default-on, beelink-x2:red:standby:none
+ir-keymap = beelink_x2
+
+[tx6]
+soc = allwinner-h6
+dtb = allwinner/sun50i-h6-tanix-tx6.dtb
+";
+
+    #[test]
+    fn parses_sections() {
+        let boards = parse_boards(BOARDS, "teste").unwrap();
+
+        assert_eq!(boards.len(), 2);
+
+        let board = &boards[0];
+        assert_eq!(board.name, "beelink-x2");
+        assert_eq!(board.model, "Beelink X2");
+        assert_eq!(board.soc.name, "h3");
+        assert_eq!(board.dtb_file, "sun8i-h3-beelink-x2.dtb");
+        assert_eq!(board.kernel_config, vec!["LEDS_GPIO", "IR_SUNXI"]);
+        assert_eq!(board.wifi_firmware, vec!["firmware-realtek", "firmware-misc-nonfree"]);
+        assert_eq!(board.ir_keymap.as_deref(), Some("beelink_x2"));
+        assert_eq!(board.packages(), vec!["firmware-realtek", "firmware-misc-nonfree", "ir-keytable"]);
+
+        // Sem model, o nome da seção é usado
+        assert_eq!(boards[1].model, "tx6");
+        assert_eq!(boards[1].soc.name, "h6");
+    }
+
+    #[test]
+    fn splits_leds_at_last_colon() {
+        let boards = parse_boards(BOARDS, "teste").unwrap();
+
+        assert_eq!(boards[0].leds, vec![
+            ("beelink-x2:blue:pwr".to_string(), "default-on".to_string()),
+            ("beelink-x2:red:standby".to_string(), "none".to_string()),
+        ]);
+
+        assert!(parse_boards("[x]\nsoc = h3\ndtb = x.dtb\nleds = working", "teste").is_err());
+        assert!(parse_boards("[x]\nsoc = h3\ndtb = x.dtb\nleds = working:", "teste").is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_definitions() {
+        let invalid = [
+            "soc = h3\n",
+            "[x]\nsoc = h3\n",
+            "[x]\ndtb = x.dtb\n",
+            "[x]\nsoc = h3\ndtb = x.dtb\ncor = preto\n",
+            "[x]\nsoc = desconhecido\ndtb = x.dtb\n",
+            "[x]\nsoc = h3\ndtb = x.dtb\nlinha sem valor\n",
+            "[x]\nsoc = h3\n = x.dtb\n",
+        ];
+
+        for contents in invalid {
+            assert!(parse_boards(contents, "teste").is_err(), "{}", contents);
+        }
+    }
+
+    #[test]
+    fn embedded_boards_are_valid() {
+        assert!(!parse_boards(EMBEDDED_BOARDS, "base embutida").unwrap().is_empty());
+    }
+
+    #[test]
+    fn local_files_override_embedded_boards() {
+        let filepath = std::env::temp_dir().join(format!("installer-boards-{}.conf", std::process::id()));
+        fs::write(&filepath, "[H96-Mini]\nmodel = H96 Mini local\nsoc = rk322x\ndtb = rk322x-h96.dtb\n").unwrap();
+
+        let boards_files = vec![filepath.to_string_lossy().to_string()];
+        let boards = load_boards(&boards_files).unwrap();
+        let board = find_board("h96-MINI", &boards_files).unwrap();
+
+        fs::remove_file(&filepath).unwrap();
+
+        assert_eq!(boards.iter().filter(|board| board.name == "h96-mini").count(), 1);
+        assert_eq!(board.model, "H96 Mini local");
+        assert_eq!(board.dtb_file, "rk322x-h96.dtb");
+        assert!(find_board("inexistente", &[]).is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,172 @@
+use std::fs;
+
+use crate::configure_boot::*;
+use crate::constants::*;
+use crate::fit_image::*;
+use crate::uimage::*;
+
+// Scripts executados pelo U-Boot: o boot.scr do distro boot e os nomes
+// procurados pelos U-Boots de fábrica da Amlogic
+pub const BOOT_SCRIPTS: &[&str] = &["boot.scr", "s905_autoscript", "aml_autoscript"];
+
+pub fn check_boot_script_name(
+    name: &str
+) -> Result<(), std::io::Error> {
+    if BOOT_SCRIPTS.contains(&name) {
+        Ok(())
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Script de boot desconhecido: {}! Scripts disponíveis: {}.", name, BOOT_SCRIPTS.join(", "))
+        ))
+    }
+}
+
+pub fn boot_script_source(
+    root_partition_path: &str,
+    boot: &BootConfig
+) -> String {
+    // O script procura a partição raiz nos dispositivos, pois os U-Boots de
+    // fábrica não definem as variáveis do distro boot
+    let partition = &root_partition_path[root_partition_path.trim_end_matches(|character: char| character.is_ascii_digit()).len()..];
+    let partition = if partition.is_empty() { "1" } else { partition };
+
+    // Endereços usados quando o ambiente do U-Boot não os define, longe do
+    // endereço de carga para que o kernel possa ser descompactado
+    let kernel_address = boot.load_address + 0x10000000;
+    let ramdisk_address = boot.load_address + 0x14000000;
+    let fdt_address = boot.load_address + 0x0f000000;
+
+    let mut script = String::new();
+
+    script += "# Gerado pelo instalador. Inicia o sistema em U-Boots sem suporte ao extlinux.conf.\n";
+    script += format!("setenv bootargs \"{}\"\n", kernel_command_line(root_partition_path, boot)).as_str();
+    script += format!("if test -z \"${{kernel_addr_r}}\"; then setenv kernel_addr_r {:#010x}; fi\n", kernel_address).as_str();
+    script += format!("if test -z \"${{ramdisk_addr_r}}\"; then setenv ramdisk_addr_r {:#010x}; fi\n", ramdisk_address).as_str();
+    script += format!("if test -z \"${{fdt_addr_r}}\"; then setenv fdt_addr_r {:#010x}; fi\n", fdt_address).as_str();
+    script += "usb start\n";
+    script += "for target in mmc usb; do\n";
+    script += "    for index in 0 1 2; do\n";
+
+    match boot.boot_format {
+        BootFormat::Legacy => {
+            script += format!("        if ext4load ${{target}} ${{index}}:{} ${{kernel_addr_r}} /boot/uImage; then\n", partition).as_str();
+            script += format!("            ext4load ${{target}} ${{index}}:{} ${{ramdisk_addr_r}} /boot/uInitrd\n", partition).as_str();
+            script += format!("            ext4load ${{target}} ${{index}}:{} ${{fdt_addr_r}} /boot/device_tree_binary.dtb\n", partition).as_str();
+            script += "            bootm ${kernel_addr_r} ${ramdisk_addr_r} ${fdt_addr_r}\n";
+        },
+        BootFormat::Fit => {
+            script += format!("        if ext4load ${{target}} ${{index}}:{} ${{kernel_addr_r}} /boot/image.itb; then\n", partition).as_str();
+            script += format!("            bootm ${{kernel_addr_r}}#{}\n", fit_configuration_name(&boot.dtb_file)).as_str();
+        },
+    }
+
+    script += "        fi\n";
+    script += "    done\n";
+    script += "done\n";
+
+    script
+}
+
+pub fn create_boot_scripts(
+    root_partition_path: &str,
+    boot: &BootConfig
+) -> Result<(), std::io::Error> {
+    // Cria o arquivo /boot/boot.cmd com o código fonte dos scripts
+    let script = boot_script_source(root_partition_path, boot);
+
+    if let Err(_) = fs::write(format!("{}/boot/boot.cmd", ROOT_MOUNT_POINT), script.as_str()) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Falha ao criar o arquivo /boot/boot.cmd!"
+        ));
+    }
+
+    // O boot.scr fica em /boot, onde o distro boot procura, e os scripts da
+    // Amlogic na raiz da partição, onde o U-Boot de fábrica procura
+    for name in &boot.scripts {
+        let script_path = match name.as_str() {
+            "boot.scr" => format!("/boot/{}", name),
+            _ => format!("/{}", name),
+        };
+
+        let header = UImageHeader {
+            os: ImageOs::Linux,
+            architecture: boot.architecture.uimage_architecture(),
+            image_type: ImageType::Script,
+            compression: ImageCompression::None,
+            load_address: 0,
+            entry_point: 0,
+            timestamp: uimage_timestamp(),
+            name: name.clone(),
+        };
+
+        if let Err(_) = fs::write(
+            format!("{}{}", ROOT_MOUNT_POINT, script_path),
+            build_uimage(&header, &script_image_data(&script))
+        ) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao criar o arquivo {}!", script_path)
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::golden::*;
+    use crate::soc_profiles::*;
+
+    fn boot_config(
+        soc: &str,
+        boot_format: BootFormat
+    ) -> BootConfig {
+        let soc = find_soc_profile(soc).unwrap();
+
+        create_boot_config(soc.architecture, Some(soc), soc.default_dtb, boot_format, &BootOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn renders_legacy_boot_script() {
+        assert_golden("boot.cmd-legacy", &boot_script_source("/dev/mmcblk2p1", &boot_config("s905x", BootFormat::Legacy)));
+    }
+
+    #[test]
+    fn renders_fit_boot_script() {
+        assert_golden("boot.cmd-fit", &boot_script_source("/dev/mmcblk2p1", &boot_config("rk3328", BootFormat::Fit)));
+    }
+
+    #[test]
+    fn uses_root_partition_number() {
+        let boot = boot_config("rk322x", BootFormat::Legacy);
+
+        assert!(boot_script_source("/dev/mmcblk0p12", &boot).contains("${target} ${index}:12 ${kernel_addr_r} /boot/uImage"));
+        assert!(boot_script_source("/dev/sda3", &boot).contains("${target} ${index}:3 ${kernel_addr_r} /boot/uImage"));
+
+        // Sem número no caminho a primeira partição é usada
+        assert!(boot_script_source("/dev/root", &boot).contains("${target} ${index}:1 ${kernel_addr_r} /boot/uImage"));
+    }
+
+    #[test]
+    fn places_default_addresses_after_load_address() {
+        let boot = boot_config("rk322x", BootFormat::Legacy);
+        let script = boot_script_source("/dev/mmcblk2p1", &boot);
+
+        assert!(script.contains(&format!("setenv kernel_addr_r {:#010x}", boot.load_address + 0x10000000)));
+        assert!(script.contains(&format!("setenv ramdisk_addr_r {:#010x}", boot.load_address + 0x14000000)));
+        assert!(script.contains(&format!("setenv fdt_addr_r {:#010x}", boot.load_address + 0x0f000000)));
+    }
+
+    #[test]
+    fn boots_fit_configuration_of_dtb() {
+        let boot = boot_config("rk3328", BootFormat::Fit);
+        let script = boot_script_source("/dev/mmcblk2p1", &boot);
+
+        assert!(script.contains(&format!("bootm ${{kernel_addr_r}}#{}\n", fit_configuration_name(&boot.dtb_file))));
+        assert!(!script.contains("/boot/uImage"));
+    }
+}
\ No newline at end of file
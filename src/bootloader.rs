@@ -0,0 +1,362 @@
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::process::Command;
+
+const SECTOR_SIZE: u64 = 512;
+
+// Área do carregador no eMMC dos SoCs Rockchip: o idbloader (TPL e SPL)
+// começa no setor 64 e o u-boot.itb no setor 16384. A primeira partição
+// começa depois dos 16 MiB reservados
+pub const ROCKCHIP_IDBLOADER_SECTOR: u64 = 64;
+pub const ROCKCHIP_UBOOT_SECTOR: u64 = 16384;
+pub const ROCKCHIP_RESERVED_SECTORS: u64 = 32768;
+
+#[derive(Default)]
+pub struct BootloaderFiles {
+    pub idbloader: Option<String>,
+    pub uboot_itb: Option<String>,
+    pub combined: Option<String>,
+}
+
+impl BootloaderFiles {
+    pub fn is_empty(&self) -> bool {
+        self.idbloader.is_none() && self.uboot_itb.is_none() && self.combined.is_none()
+    }
+}
+
+pub struct BootloaderImage {
+    pub filepath: String,
+    pub sector: u64,
+    pub data: Vec<u8>,
+}
+
+impl BootloaderImage {
+    pub fn end_sector(&self) -> u64 {
+        self.sector + (self.data.len() as u64).div_ceil(SECTOR_SIZE)
+    }
+}
+
+pub fn load_bootloader_images(
+    files: &BootloaderFiles
+) -> Result<Vec<BootloaderImage>, std::io::Error> {
+    // O u-boot-rockchip.bin já contém o idbloader e o u-boot.itb nas
+    // posições certas, então substitui os dois arquivos separados
+    let placements: Vec<(&String, u64)> = match (&files.combined, &files.idbloader, &files.uboot_itb) {
+        (Some(combined), None, None) => vec![(combined, ROCKCHIP_IDBLOADER_SECTOR)],
+        (None, Some(idbloader), Some(uboot_itb)) => vec![
+            (idbloader, ROCKCHIP_IDBLOADER_SECTOR),
+            (uboot_itb, ROCKCHIP_UBOOT_SECTOR),
+        ],
+        (Some(_), _, _) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "O --uboot-rockchip não pode ser usado junto com --idbloader ou --uboot-itb!"
+            ));
+        },
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "O --idbloader e o --uboot-itb devem ser informados juntos!"
+            ));
+        }
+    };
+
+    let mut images: Vec<BootloaderImage> = Vec::new();
+
+    for (filepath, sector) in placements {
+        let data = match fs::read(filepath) {
+            Ok(data) => data,
+            Err(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("Falha ao ler o arquivo {}!", filepath)
+                ));
+            }
+        };
+
+        if data.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("O arquivo {} está vazio!", filepath)
+            ));
+        }
+
+        images.push(BootloaderImage {
+            filepath: filepath.clone(),
+            sector,
+            data,
+        });
+    }
+
+    // Cada imagem deve caber antes da próxima e dentro da área reservada
+    for (index, image) in images.iter().enumerate() {
+        let limit = images
+            .get(index + 1)
+            .map(|next| next.sector)
+            .unwrap_or(ROCKCHIP_RESERVED_SECTORS);
+
+        if image.end_sector() > limit {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!(
+                    "O arquivo {} ocupa até o setor {}, mas o limite é o setor {}!",
+                    image.filepath,
+                    image.end_sector(),
+                    limit
+                )
+            ));
+        }
+    }
+
+    Ok(images)
+}
+
+pub fn check_partition_layout(
+    root_partition_path: &str,
+    images: &[BootloaderImage]
+) -> Result<(), std::io::Error> {
+    // Confere pelo sysfs, em setores de 512 bytes, se a primeira partição
+    // começa depois da área do carregador
+    let partition_name = root_partition_path.rsplit('/').next().unwrap_or(root_partition_path);
+    let start_path = format!("/sys/class/block/{}/start", partition_name);
+
+    let start: u64 = match fs::read_to_string(start_path.as_str()).map(|start| start.trim().parse()) {
+        Ok(Ok(start)) => start,
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao obter o início da partição {}!", root_partition_path)
+            ));
+        }
+    };
+
+    let end = images.iter().map(|image| image.end_sector()).max().unwrap_or(0);
+
+    if start < end {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!(
+                "A partição {} começa no setor {} e sobrepõe a área do carregador, que vai até o setor {}!",
+                root_partition_path,
+                start,
+                end
+            )
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn write_bootloader(
+    storage_device_path: &str,
+    images: &[BootloaderImage]
+) -> Result<(), std::io::Error> {
+    let mut device = match OpenOptions::new().read(true).write(true).open(storage_device_path) {
+        Ok(device) => device,
+        Err(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao abrir o dispositivo {}!", storage_device_path)
+            ));
+        }
+    };
+
+    // Grava cada imagem na sua posição
+    for image in images {
+        device.seek(SeekFrom::Start(image.sector * SECTOR_SIZE))?;
+
+        if let Err(_) = device.write_all(&image.data) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao gravar o arquivo {} no setor {}!", image.filepath, image.sector)
+            ));
+        }
+    }
+
+    device.sync_all()?;
+    drop(device);
+
+    // Descarta o cache do dispositivo para que a leitura venha do eMMC
+    let output = Command::new("blockdev")
+        .arg("--flushbufs")
+        .arg(storage_device_path)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao descartar o cache do dispositivo {}!", storage_device_path)
+        ));
+    }
+
+    // Lê de volta e compara com os arquivos gravados
+    let mut device = OpenOptions::new().read(true).open(storage_device_path)?;
+
+    for image in images {
+        let mut written = vec![0; image.data.len()];
+
+        device.seek(SeekFrom::Start(image.sector * SECTOR_SIZE))?;
+        device.read_exact(&mut written)?;
+
+        if written != image.data {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("O conteúdo gravado no setor {} difere do arquivo {}!", image.sector, image.filepath)
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("installer-bootloader-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn bootloader_file(
+        dir: &std::path::Path,
+        name: &str,
+        size: u64
+    ) -> Option<String> {
+        let filepath = dir.join(name);
+        fs::write(&filepath, vec![0xa5; size as usize]).unwrap();
+        Some(filepath.to_string_lossy().to_string())
+    }
+
+    fn load_error(files: &BootloaderFiles) -> String {
+        match load_bootloader_images(files) {
+            Ok(_) => panic!("As imagens do carregador deveriam ser recusadas"),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn loads_combined_image() {
+        let dir = temp_dir("combined");
+        let files = BootloaderFiles {
+            combined: bootloader_file(&dir, "u-boot-rockchip.bin", (ROCKCHIP_RESERVED_SECTORS - ROCKCHIP_IDBLOADER_SECTOR) * SECTOR_SIZE),
+            ..Default::default()
+        };
+
+        let images = load_bootloader_images(&files).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].sector, ROCKCHIP_IDBLOADER_SECTOR);
+        assert_eq!(images[0].end_sector(), ROCKCHIP_RESERVED_SECTORS);
+    }
+
+    #[test]
+    fn loads_separate_images() {
+        let dir = temp_dir("separate");
+        let files = BootloaderFiles {
+            idbloader: bootloader_file(&dir, "idbloader.img", (ROCKCHIP_UBOOT_SECTOR - ROCKCHIP_IDBLOADER_SECTOR) * SECTOR_SIZE),
+            uboot_itb: bootloader_file(&dir, "u-boot.itb", 1000 * SECTOR_SIZE + 1),
+            ..Default::default()
+        };
+
+        let images = load_bootloader_images(&files).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(images.len(), 2);
+        assert_eq!((images[0].sector, images[0].end_sector()), (ROCKCHIP_IDBLOADER_SECTOR, ROCKCHIP_UBOOT_SECTOR));
+        assert_eq!((images[1].sector, images[1].end_sector()), (ROCKCHIP_UBOOT_SECTOR, ROCKCHIP_UBOOT_SECTOR + 1001));
+    }
+
+    #[test]
+    fn rejects_invalid_combinations() {
+        let dir = temp_dir("combinations");
+        let combined = bootloader_file(&dir, "u-boot-rockchip.bin", SECTOR_SIZE);
+        let idbloader = bootloader_file(&dir, "idbloader.img", SECTOR_SIZE);
+        let uboot_itb = bootloader_file(&dir, "u-boot.itb", SECTOR_SIZE);
+
+        let mixed = [
+            BootloaderFiles { combined: combined.clone(), idbloader: idbloader.clone(), uboot_itb: None },
+            BootloaderFiles { combined: combined.clone(), idbloader: None, uboot_itb: uboot_itb.clone() },
+            BootloaderFiles { combined: combined.clone(), idbloader: idbloader.clone(), uboot_itb: uboot_itb.clone() },
+        ];
+
+        for files in &mixed {
+            assert!(load_error(files).contains("não pode ser usado junto"));
+        }
+
+        let incomplete = [
+            BootloaderFiles { combined: None, idbloader: idbloader.clone(), uboot_itb: None },
+            BootloaderFiles { combined: None, idbloader: None, uboot_itb: uboot_itb.clone() },
+            BootloaderFiles::default(),
+        ];
+
+        for files in &incomplete {
+            assert!(load_error(files).contains("devem ser informados juntos"));
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_idbloader_overrunning_uboot() {
+        let dir = temp_dir("overrun");
+        let files = BootloaderFiles {
+            idbloader: bootloader_file(&dir, "idbloader.img", (ROCKCHIP_UBOOT_SECTOR - ROCKCHIP_IDBLOADER_SECTOR) * SECTOR_SIZE + 1),
+            uboot_itb: bootloader_file(&dir, "u-boot.itb", SECTOR_SIZE),
+            ..Default::default()
+        };
+
+        let error = load_error(&files);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(error.contains("idbloader.img"));
+        assert!(error.contains(&format!("o limite é o setor {}", ROCKCHIP_UBOOT_SECTOR)));
+    }
+
+    #[test]
+    fn rejects_images_beyond_reserved_area() {
+        let dir = temp_dir("reserved");
+        let separate = BootloaderFiles {
+            idbloader: bootloader_file(&dir, "idbloader.img", SECTOR_SIZE),
+            uboot_itb: bootloader_file(&dir, "u-boot.itb", (ROCKCHIP_RESERVED_SECTORS - ROCKCHIP_UBOOT_SECTOR) * SECTOR_SIZE + 1),
+            ..Default::default()
+        };
+        let combined = BootloaderFiles {
+            combined: bootloader_file(&dir, "u-boot-rockchip.bin", (ROCKCHIP_RESERVED_SECTORS - ROCKCHIP_IDBLOADER_SECTOR) * SECTOR_SIZE + 1),
+            ..Default::default()
+        };
+
+        let separate_error = load_error(&separate);
+        let combined_error = load_error(&combined);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(separate_error.contains("u-boot.itb"));
+        assert!(separate_error.contains(&format!("o limite é o setor {}", ROCKCHIP_RESERVED_SECTORS)));
+        assert!(combined_error.contains("u-boot-rockchip.bin"));
+        assert!(combined_error.contains(&format!("o limite é o setor {}", ROCKCHIP_RESERVED_SECTORS)));
+    }
+
+    #[test]
+    fn rejects_empty_and_missing_files() {
+        let dir = temp_dir("empty");
+        let empty = BootloaderFiles {
+            idbloader: bootloader_file(&dir, "idbloader.img", SECTOR_SIZE),
+            uboot_itb: bootloader_file(&dir, "u-boot.itb", 0),
+            ..Default::default()
+        };
+        let missing = BootloaderFiles {
+            combined: Some(dir.join("ausente.bin").to_string_lossy().to_string()),
+            ..Default::default()
+        };
+
+        let empty_error = load_error(&empty);
+        let missing_error = load_error(&missing);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(empty_error.contains("u-boot.itb está vazio"));
+        assert!(missing_error.contains("Falha ao ler o arquivo"));
+    }
+}
\ No newline at end of file
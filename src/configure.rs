@@ -1,6 +1,7 @@
 use std::fs;
 use std::io;
 use std::io::Write;
+use std::path::Path;
 use regex::Regex;
 use std::process::Command;
 use std::process::Stdio;
@@ -74,75 +75,130 @@ pub fn get_root_password() -> String {
     password.replace("\n", "")
 }
 
-pub fn set_hostname(hostname: &str) -> Result<(), std::io::Error>  {
-    // Cria o arquivo /etc/hostname
-    let filepath = format!("{}/etc/hostname", ROOT_MOUNT_POINT);
-
-    if let Err(_) = fs::write(filepath, hostname) {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "Falha ao criar o arquivo /etc/hostname!"
-        ));
-    }
+pub struct SystemConfig {
+    pub hostname: String,
+    pub suite: String,
+    pub mirror: String,
+    pub security_mirror: Option<String>,
+    pub root_device: String,
+}
 
-    Ok(())
+pub fn render_hostname(system: &SystemConfig) -> String {
+    format!("{}\n", system.hostname)
 }
 
-pub fn set_hosts(hostname: &str) -> Result<(), std::io::Error>  {
-    // Cria o arquivo /etc/hosts
+pub fn render_hosts(system: &SystemConfig) -> String {
     let mut hosts = String::new();
 
     hosts += "127.0.0.1 localhost.localdomain localhost\n";
     hosts += "::1 localhost.localdomain localhost\n";
-    hosts += format!("127.0.1.1 {0}.localdomain {0}", hostname).as_str();
+    hosts += format!("127.0.1.1 {0}.localdomain {0}\n", system.hostname).as_str();
 
-    let filepath = format!("{}/etc/hosts", ROOT_MOUNT_POINT);
+    hosts
+}
 
-    if let Err(_) = fs::write(filepath, hosts) {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "Falha ao criar o arquivo /etc/hosts!"
-        ));
-    }
+pub fn render_fstab(system: &SystemConfig) -> String {
+    let mut fstab = String::new();
 
-    Ok(())
+    fstab += "# <sistema de arquivos>\t<ponto de montagem>\t<tipo>\t<opções>\t<dump>\t<pass>\n";
+    fstab += format!("{}\t/\text4\tdefaults\t0\t1\n", system.root_device).as_str();
+
+    fstab
 }
 
-pub fn set_fstab(storage_device_path: &str) -> Result<(), std::io::Error>  {
-    // Cria o arquivo /etc/fstab
-    let fstab = format!("{}\text4\tdefaults\t0\t0", storage_device_path);
+pub fn render_sources_list(system: &SystemConfig) -> String {
+    let suite = system.suite.as_str();
+    let mirror = system.mirror.trim_end_matches('/');
+
+    // O repositório de segurança fica ao lado do espelho, como em
+    // deb.debian.org/debian-security, salvo indicação em contrário
+    let security_mirror = match &system.security_mirror {
+        Some(security_mirror) => security_mirror.clone(),
+        None => format!("{}-security/", mirror),
+    };
+
+    // O non-free-firmware existe a partir do bookworm, e o repositório de
+    // segurança mudou de <suite>/updates para <suite>-security no bullseye
+    let components = match suite {
+        "buster" | "bullseye" => "main non-free",
+        _ => "main non-free non-free-firmware",
+    };
+    let security_suite = match suite {
+        "buster" => Some(format!("{}/updates", suite)),
+        "sid" | "unstable" => None,
+        _ => Some(format!("{}-security", suite)),
+    };
 
-    let filepath = format!("{}/etc/fstab", ROOT_MOUNT_POINT);
+    let mut sources_list = String::new();
 
-    if let Err(_) = fs::write(filepath, fstab) {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "Falha ao criar o arquivo /etc/fstab!"
-        ));
+    sources_list += format!("deb {} {} {}\n", mirror, suite, components).as_str();
+    sources_list += format!("deb-src {} {} {}\n", mirror, suite, components).as_str();
+
+    // O sid não tem atualizações de segurança nem o repositório updates
+    if let Some(security_suite) = security_suite {
+        sources_list += "\n";
+        sources_list += format!("deb {} {} {}\n", security_mirror, security_suite, components).as_str();
+        sources_list += format!("deb-src {} {} {}\n", security_mirror, security_suite, components).as_str();
+        sources_list += "\n";
+        sources_list += format!("deb {} {}-updates {}\n", mirror, suite, components).as_str();
+        sources_list += format!("deb-src {} {}-updates {}\n", mirror, suite, components).as_str();
     }
 
-    Ok(())
+    sources_list
 }
 
-pub fn set_sources_list() -> Result<(), std::io::Error> {
-    // Cria o arquivo /etc/apt/sources.list
-    let mut sources_list = String::new();
+pub fn set_hostname(
+    target_root: &str,
+    system: &SystemConfig
+) -> Result<(), std::io::Error> {
+    // Cria o arquivo /etc/hostname
+    write_config_file(target_root, "/etc/hostname", &render_hostname(system))
+}
 
-    sources_list += "deb http://debian.c3sl.ufpr.br/debian bookworm main non-free non-free-firmware\n";
-    sources_list += "deb-src http://debian.c3sl.ufpr.br/debian bookworm main non-free non-free-firmware\n";
-    sources_list += "\n";
-    sources_list += "deb http://debian.c3sl.ufpr.br/debian-security/ bookworm-security main non-free non-free-firmware\n";
-    sources_list += "deb-src http://debian.c3sl.ufpr.br/debian-security/ bookworm-security main non-free non-free-firmware\n";
-    sources_list += "\n";
-    sources_list += "deb http://debian.c3sl.ufpr.br/debian bookworm-updates main non-free non-free-firmware\n";
-    sources_list += "deb-src http://debian.c3sl.ufpr.br/debian bookworm-updates main non-free non-free-firmware\n";
+pub fn set_hosts(
+    target_root: &str,
+    system: &SystemConfig
+) -> Result<(), std::io::Error> {
+    // Cria o arquivo /etc/hosts
+    write_config_file(target_root, "/etc/hosts", &render_hosts(system))
+}
 
-    let filepath = format!("{}/etc/apt/sources.list", ROOT_MOUNT_POINT);
+pub fn set_fstab(
+    target_root: &str,
+    system: &SystemConfig
+) -> Result<(), std::io::Error> {
+    // Cria o arquivo /etc/fstab
+    write_config_file(target_root, "/etc/fstab", &render_fstab(system))
+}
 
-    if let Err(_) = fs::write(filepath, sources_list) {
+pub fn set_sources_list(
+    target_root: &str,
+    system: &SystemConfig
+) -> Result<(), std::io::Error> {
+    // Cria o arquivo /etc/apt/sources.list
+    write_config_file(target_root, "/etc/apt/sources.list", &render_sources_list(system))
+}
+
+fn write_config_file(
+    target_root: &str,
+    filepath: &str,
+    contents: &str
+) -> Result<(), std::io::Error> {
+    let target_path = Path::new(target_root).join(filepath.trim_start_matches('/'));
+
+    if let Some(parent) = target_path.parent() {
+        if let Err(_) = fs::create_dir_all(parent) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao criar o diretório de {}!", filepath)
+            ));
+        }
+    }
+
+    if let Err(_) = fs::write(target_path, contents) {
         return Err(std::io::Error::new(
             std::io::ErrorKind::Other,
-            "Falha ao criar o arquivo /etc/apt/sources.list!"
+            format!("Falha ao criar o arquivo {}!", filepath)
         ));
     }
 
@@ -185,4 +241,77 @@ pub fn umount_root_partition() -> Result<(), std::io::Error> {
     }
     
     Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::golden::*;
+
+    fn system_config(
+        suite: &str
+    ) -> SystemConfig {
+        SystemConfig {
+            hostname: "tvbox".to_string(),
+            suite: suite.to_string(),
+            mirror: DEFAULT_TARGET_MIRROR.to_string(),
+            security_mirror: None,
+            root_device: "/dev/mmcblk2p1".to_string(),
+        }
+    }
+
+    #[test]
+    fn renders_hostname_and_hosts() {
+        let system = system_config("bookworm");
+
+        assert_golden("hostname", &render_hostname(&system));
+        assert_golden("hosts", &render_hosts(&system));
+    }
+
+    #[test]
+    fn renders_fstab() {
+        assert_golden("fstab", &render_fstab(&system_config("bookworm")));
+    }
+
+    #[test]
+    fn renders_sources_list_for_each_suite() {
+        for suite in ["buster", "bullseye", "bookworm", "trixie", "sid"] {
+            assert_golden(&format!("sources.list-{}", suite), &render_sources_list(&system_config(suite)));
+        }
+    }
+
+    #[test]
+    fn renders_sources_list_with_custom_mirror() {
+        let mut system = system_config("bookworm");
+        system.mirror = "http://deb.debian.org/debian/".to_string();
+
+        assert_golden("sources.list-custom-mirror", &render_sources_list(&system));
+    }
+
+    #[test]
+    fn renders_sources_list_with_security_mirror() {
+        let mut system = system_config("bookworm");
+        system.security_mirror = Some("http://security.debian.org/debian-security".to_string());
+
+        assert_golden("sources.list-security-mirror", &render_sources_list(&system));
+    }
+
+    #[test]
+    fn writes_files_into_target_root() {
+        let target_root = std::env::temp_dir().join(format!("installer-configure-{}", std::process::id()));
+        let target = target_root.to_str().unwrap();
+        let system = system_config("bookworm");
+
+        set_hostname(target, &system).unwrap();
+        set_hosts(target, &system).unwrap();
+        set_fstab(target, &system).unwrap();
+        set_sources_list(target, &system).unwrap();
+
+        assert_eq!(fs::read_to_string(target_root.join("etc/hostname")).unwrap(), render_hostname(&system));
+        assert_eq!(fs::read_to_string(target_root.join("etc/hosts")).unwrap(), render_hosts(&system));
+        assert_eq!(fs::read_to_string(target_root.join("etc/fstab")).unwrap(), render_fstab(&system));
+        assert_eq!(fs::read_to_string(target_root.join("etc/apt/sources.list")).unwrap(), render_sources_list(&system));
+
+        fs::remove_dir_all(target_root).unwrap();
+    }
 }
\ No newline at end of file
@@ -1,19 +1,296 @@
+use std::cmp::Ordering;
 use std::fs;
+use std::os::unix::fs::symlink;
 use std::path::Path;
 use std::process::Command;
 
 use crate::constants::*;
+use crate::fdt::*;
+use crate::fit_image::*;
+use crate::install::*;
+use crate::kernel::*;
+use crate::overlays::*;
+use crate::uimage::*;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BootFormat {
+    Legacy,
+    Fit,
+}
+
+impl BootFormat {
+    pub fn from_name(name: &str) -> Result<BootFormat, std::io::Error> {
+        match name {
+            "legacy" => Ok(BootFormat::Legacy),
+            "fit" => Ok(BootFormat::Fit),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Formato de boot desconhecido: {}!", name)
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BootFormat::Legacy => "legacy",
+            BootFormat::Fit => "fit",
+        }
+    }
+
+    pub fn dependencies(&self) -> &'static [&'static str] {
+        // O FIT é gerado pelo mkimage, que usa o dtc
+        match self {
+            BootFormat::Legacy => &[],
+            BootFormat::Fit => &["u-boot-tools", "device-tree-compiler"],
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CommandLineOptions {
+    pub console: Option<String>,
+    pub loglevel: Option<u32>,
+    pub extra_args: Vec<String>,
+    pub removed_args: Vec<String>,
+}
+
+pub struct BootOptions {
+    pub command_line: CommandLineOptions,
+    pub overlays: Vec<String>,
+    pub merge_overlays: bool,
+    pub scripts: Option<Vec<String>>,
+    pub timeout: u32,
+}
+
+impl Default for BootOptions {
+    fn default() -> BootOptions {
+        BootOptions {
+            command_line: CommandLineOptions::default(),
+            overlays: Vec::new(),
+            merge_overlays: false,
+            scripts: None,
+            timeout: DEFAULT_BOOT_TIMEOUT,
+        }
+    }
+}
+
+pub struct BootConfig {
+    pub architecture: KernelArchitecture,
+    pub dtb_file: String,
+    pub boot_format: BootFormat,
+    pub load_address: u32,
+    pub console: Option<String>,
+    pub loglevel: u32,
+    pub extra_bootargs: Vec<String>,
+    pub custom_bootargs: Vec<String>,
+    pub removed_bootargs: Vec<String>,
+    pub overlays: Vec<String>,
+    pub merge_overlays: bool,
+    pub scripts: Vec<String>,
+    pub timeout: u32,
+}
+
+impl BootConfig {
+    pub fn merges_overlays(&self) -> bool {
+        // A imagem FIT é carregada pelo bootm, que não aplica FDTOVERLAYS,
+        // então os overlays são sempre aplicados na instalação
+        !self.overlays.is_empty() && (self.merge_overlays || self.boot_format == BootFormat::Fit)
+    }
+}
+
+pub fn kernel_command_line(
+    root_partition_path: &str,
+    boot: &BootConfig
+) -> String {
+    // Parâmetros do kernel usados pelo extlinux.conf e pelo boot.scr
+    let mut args: Vec<String> = vec![
+        "earlyprintk".to_string(),
+        format!("root={}", root_partition_path),
+        "rootwait".to_string(),
+        "rootfstype=ext4".to_string(),
+        "init=/sbin/init".to_string(),
+        format!("loglevel={}", boot.loglevel),
+    ];
+
+    if let Some(console) = &boot.console {
+        args.push(format!("console={}", console));
+    }
+
+    args.extend(boot.extra_bootargs.iter().cloned());
+
+    // Os parâmetros do usuário substituem os padrões com o mesmo nome,
+    // exceto console=, que o kernel aceita várias vezes. Assim a console
+    // serial, que tem um getty, continua recebendo as mensagens
+    args.retain(|arg| {
+        let name = bootarg_name(arg);
+
+        !boot.removed_bootargs.iter().any(|removed| removed == name)
+            && (name == "console" || !boot.custom_bootargs.iter().any(|custom| bootarg_name(custom) == name))
+    });
+
+    for custom in &boot.custom_bootargs {
+        if !args.contains(custom) {
+            args.push(custom.clone());
+        }
+    }
+
+    args.join(" ")
+}
+
+fn bootarg_name(arg: &str) -> &str {
+    // Nome de um parâmetro do kernel, sem o valor (ex.: console=ttyS2 -> console)
+    arg.split('=').next().unwrap_or(arg)
+}
+
+pub fn configure_serial_getty(
+    boot: &BootConfig
+) -> Result<(), std::io::Error> {
+    let console = match &boot.console {
+        Some(console) => console,
+        None => return Ok(()),
+    };
+
+    // A console tem o formato <tty>[,<velocidade><paridade><bits>], como
+    // ttyS2,1500000n8
+    let (tty, options) = console.split_once(',').unwrap_or((console.as_str(), ""));
+    let baud_rate: String = options.chars().take_while(|character| character.is_ascii_digit()).collect();
+    let unit = format!("serial-getty@{}.service", tty);
+
+    // O serial-getty só tenta velocidades até 115200, então a velocidade da
+    // console é fixada por um drop-in
+    if !baud_rate.is_empty() {
+        let dropin_dir = format!("{}/etc/systemd/system/{}.d", ROOT_MOUNT_POINT, unit);
+        let dropin = format!(
+            "[Service]\nExecStart=\nExecStart=-/sbin/agetty -o '-p -- \\\\u' --keep-baud {} - $TERM\n",
+            baud_rate
+        );
+
+        if fs::create_dir_all(dropin_dir.as_str()).is_err()
+            || fs::write(format!("{}/{}", dropin_dir, SERIAL_GETTY_DROPIN), dropin).is_err()
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao criar o arquivo /etc/systemd/system/{}.d/{}!", unit, SERIAL_GETTY_DROPIN)
+            ));
+        }
+    }
+
+    run_systemctl("enable", &unit)
+}
+
+// Arquivos de /boot que definem as entradas do extlinux.conf
+#[derive(Default)]
+pub struct InstalledBootFiles {
+    pub fallback: bool,
+    pub fit_images: Vec<String>,
+    pub kernel_releases: Vec<String>,
+}
+
+pub fn read_installed_boot_files(
+    target_root: &str,
+    boot: &BootConfig
+) -> Result<InstalledBootFiles, std::io::Error> {
+    let image_name = boot.architecture.image_name();
+    let boot_dir = Path::new(target_root).join("boot");
+    let kernel_releases = installed_kernel_releases(target_root, image_name)?;
+
+    // Imagens FIT presentes, pois kernels instalados antes sem FIT continuam
+    // com os arquivos separados
+    let mut fit_images: Vec<String> = vec!["image.itb".to_string(), "image.itb.old".to_string()];
+    fit_images.extend(kernel_releases.iter().map(|kernel_release| format!("image-{}.itb", kernel_release)));
+    fit_images.retain(|fit_image| fs::symlink_metadata(boot_dir.join(fit_image)).is_ok());
+
+    Ok(InstalledBootFiles {
+        fallback: fs::symlink_metadata(boot_dir.join(format!("{}.old", image_name))).is_ok(),
+        fit_images,
+        kernel_releases,
+    })
+}
+
+pub fn render_extlinux_configuration(
+    root_partition_path: &str,
+    boot: &BootConfig,
+    installed: &InstalledBootFiles
+) -> String {
+    let image_name = boot.architecture.image_name();
+    let append = kernel_command_line(root_partition_path, boot);
+    let fit_configuration = fit_configuration_name(&boot.dtb_file);
+
+    // Com FIT, o kernel, o initrd e o DTB vêm da configuração do aparelho
+    // dentro da imagem .itb. Kernels instalados antes sem FIT continuam com
+    // os arquivos separados
+    let boot_files = |image: &str, initrd: &str, dtb_dir: &str, fdt: &str, fit_image: &str| -> String {
+        if boot.boot_format == BootFormat::Fit && installed.fit_images.iter().any(|installed_image| installed_image == fit_image) {
+            return format!("  LINUX ../{}#{}\n", fit_image, fit_configuration);
+        }
+
+        let mut lines = format!("  LINUX ../{}\n  INITRD ../{}\n  FDT ../{}\n", image, initrd, fdt);
+
+        // Overlays aplicados pelo próprio U-Boot
+        if !boot.overlays.is_empty() && !boot.merges_overlays() {
+            let overlays: Vec<String> = boot.overlays
+                .iter()
+                .map(|overlay| format!("../{}/overlays/{}", dtb_dir, overlay_file_name(overlay)))
+                .collect();
+
+            lines += format!("  FDTOVERLAYS {}\n", overlays.join(" ")).as_str();
+        }
+
+        lines
+    };
+
+    let mut extlinux = String::new();
+
+    extlinux += "MENU TITLE Debian GNU/Linux\n";
+    extlinux += format!("TIMEOUT {}\n", boot.timeout * 10).as_str();
+    extlinux += "DEFAULT linux\n";
+
+    // Kernel padrão
+    extlinux += "\nLABEL linux\n";
+    extlinux += "  MENU LABEL Debian GNU/Linux\n";
+    extlinux += &boot_files(image_name, "initrd.img", "dtb", "device_tree_binary.dtb", "image.itb");
+    extlinux += format!("  APPEND {}\n", append).as_str();
+
+    // Kernel anterior, quando houver
+    if installed.fallback {
+        extlinux += "\nLABEL fallback\n";
+        extlinux += "  MENU LABEL Debian GNU/Linux (kernel anterior)\n";
+        extlinux += &boot_files(&format!("{}.old", image_name), "initrd.img.old", "dtb.old", "device_tree_binary.dtb.old", "image.itb.old");
+        extlinux += format!("  APPEND {}\n", append).as_str();
+    }
+
+    // Modo de recuperação (monousuário)
+    extlinux += "\nLABEL recovery\n";
+    extlinux += "  MENU LABEL Debian GNU/Linux (modo de recuperação)\n";
+    extlinux += &boot_files(image_name, "initrd.img", "dtb", "device_tree_binary.dtb", "image.itb");
+    extlinux += format!("  APPEND {} single\n", append).as_str();
+
+    // Uma entrada para cada kernel instalado
+    for kernel_release in &installed.kernel_releases {
+        extlinux += format!("\nLABEL linux-{}\n", kernel_release).as_str();
+        extlinux += format!("  MENU LABEL Debian GNU/Linux, Linux {}\n", kernel_release).as_str();
+        extlinux += &boot_files(
+            &format!("{}-{}", image_name, kernel_release),
+            &format!("initrd.img-{}", kernel_release),
+            &format!("dtb-{}", kernel_release),
+            &format!("dtb-{}/{}", kernel_release, boot.dtb_file),
+            &format!("image-{}.itb", kernel_release)
+        );
+        extlinux += format!("  APPEND {}\n", append).as_str();
+    }
+
+    extlinux
+}
 
 pub fn create_extlinux_configuration_file(
-    root_partition_path: &str
+    target_root: &str,
+    root_partition_path: &str,
+    boot: &BootConfig
 ) -> Result<(), std::io::Error> {
     // Cria o caminho /boot/extlinux
-    let output = Command::new("mkdir")
-        .arg("-p")
-        .arg(format!("{}/boot/extlinux", ROOT_MOUNT_POINT).as_str())
-        .output()?;
-    
-    if !output.status.success() {
+    let extlinux_dir = Path::new(target_root).join("boot/extlinux");
+
+    if let Err(_) = fs::create_dir_all(&extlinux_dir) {
         return Err(std::io::Error::new(
             std::io::ErrorKind::Other,
             "Falha ao criar o caminho /boot/extlinux!"
@@ -21,19 +298,10 @@ pub fn create_extlinux_configuration_file(
     }
 
     // Cria o arquivo /boot/extlinux/extlinux.conf
-    let mut extlinux = String::new();
-
-    extlinux += "LABEL Linux\n";
-    extlinux += "  LINUX ../zImage\n";
-    extlinux += "  INITRD ../initrd.img\n";
-    extlinux += "  FDT ../device_tree_binary.dtb\n";
-    extlinux += format!(
-        "  APPEND earlyprintk root={} rootwait rootfstype=ext4 init=/sbin/init loglevel=0\n", 
-        root_partition_path).as_str();
-
-    let filepath = format!("{}/boot/extlinux/extlinux.conf", ROOT_MOUNT_POINT);
+    let installed = read_installed_boot_files(target_root, boot)?;
+    let extlinux = render_extlinux_configuration(root_partition_path, boot, &installed);
 
-    if let Err(_) = fs::write(filepath, extlinux) {
+    if let Err(_) = fs::write(extlinux_dir.join("extlinux.conf"), extlinux) {
         return Err(std::io::Error::new(
             std::io::ErrorKind::Other,
             "Falha ao criar o arquivo /boot/extlinux/extlinux.conf!"
@@ -43,14 +311,82 @@ pub fn create_extlinux_configuration_file(
     Ok(())
 }
 
+fn installed_kernel_releases(
+    target_root: &str,
+    image_name: &str
+) -> Result<Vec<String>, std::io::Error> {
+    // Lista as versões com imagem <zImage|Image>-<versão> em /boot
+    let boot_dir = Path::new(target_root).join("boot");
+    let prefix = format!("{}-", image_name);
+
+    let entries = match fs::read_dir(boot_dir) {
+        Ok(entries) => entries,
+        Err(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "Falha ao ler o diretório /boot!"
+            ));
+        }
+    };
+
+    let mut releases: Vec<String> = Vec::new();
+
+    for entry in entries.flatten() {
+        let filename = entry.file_name().to_string_lossy().to_string();
+
+        if let Some(release) = filename.strip_prefix(&prefix) {
+            if entry.path().is_file() {
+                releases.push(release.to_string());
+            }
+        }
+    }
+
+    releases.sort_by(|a, b| compare_kernel_releases(a, b));
+
+    Ok(releases)
+}
+
+fn compare_kernel_releases(
+    a: &str,
+    b: &str
+) -> Ordering {
+    // Compara as versões trecho a trecho, com os números pelo valor, para
+    // que 6.10 venha depois de 6.9
+    let chunks = |release: &str| -> Vec<String> {
+        let mut chunks: Vec<String> = Vec::new();
+
+        for character in release.chars() {
+            match chunks.last_mut() {
+                Some(chunk) if chunk.chars().all(|c| c.is_ascii_digit()) == character.is_ascii_digit() => chunk.push(character),
+                _ => chunks.push(character.to_string()),
+            }
+        }
+
+        chunks
+    };
+
+    for (chunk_a, chunk_b) in chunks(a).iter().zip(chunks(b).iter()) {
+        let ordering = match (chunk_a.parse::<u64>(), chunk_b.parse::<u64>()) {
+            (Ok(number_a), Ok(number_b)) => number_a.cmp(&number_b),
+            _ => chunk_a.cmp(chunk_b),
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    chunks(a).len().cmp(&chunks(b).len())
+}
+
 pub fn copy_boot_files(
-    kernel_path: &str,
-    kernel_release: &str
+    kernel: &KernelFiles
 ) -> Result<(), std::io::Error> {
+    let kernel_release = kernel.release.as_str();
 
     // ARQUIVOS DTB
 
-    let dtb_source_dir = format!("{}/arch/arm/boot/dts", kernel_path);
+    let dtb_source_dir = kernel.dtb_dir.as_str();
     let dtb_destination_dir = format!("{}/boot/dtb-{}", ROOT_MOUNT_POINT, kernel_release);
 
     // Cria o caminho /boot/dtb-<kernel_release>
@@ -61,45 +397,42 @@ pub fn copy_boot_files(
         ));
     }
 
-    // Copia os arquivos DTB para /boot/dtb-<kernel_release>
-    if let Ok(entries) = fs::read_dir(dtb_source_dir) {
-        for entry in entries {
-            if let Ok(entry) = entry {
-                if let Some(extension) = entry.path().extension() {
-                    if extension == "dtb" {
-                        let destination_file = Path::new(
-                            dtb_destination_dir.as_str()
-                        ).join(entry.file_name());
-
-                        if let Err(_) = fs::copy(entry.path(), destination_file) {
-                            return Err(std::io::Error::new(
-                                std::io::ErrorKind::Other,
-                                format!("Falha ao copiar os arquivos DTB para /boot/dtb-{}!", kernel_release)
-                            ));
-                        }
-                    }
-                }
-            }
-        }
-    } else {
+    // Copia os arquivos DTB para /boot/dtb-<kernel_release>, mantendo os
+    // subdiretórios de fabricante (rockchip/, amlogic/...)
+    if copy_dtb_files(Path::new(dtb_source_dir), Path::new(dtb_destination_dir.as_str()))? == 0 {
         return Err(std::io::Error::new(
             std::io::ErrorKind::Other,
-            "Falha ao ler o diretório de origem dos arquivos DTB!"
+            "Nenhum arquivo DTB foi encontrado no diretório de origem!"
         ));
     }
 
-    // Copia o arquivo zImage
+    // Copia a imagem do kernel (zImage ou Image)
+    let image_name = kernel.architecture.image_name();
     let destination_file = Path::new(
         format!("{}/boot", ROOT_MOUNT_POINT).as_str()
-    ).join(format!("zImage-{}", kernel_release));
+    ).join(format!("{}-{}", image_name, kernel_release));
 
-    if let Err(_) = fs::copy(
-        format!("{}/arch/arm/boot/zImage", kernel_path), 
+    if is_gzip_file(&kernel.image)? {
+        // O U-Boot carrega a Image do ARM64 sem compressão
+        let output = Command::new("gzip")
+            .arg("--decompress")
+            .arg("--stdout")
+            .arg(&kernel.image)
+            .output()?;
+
+        if !output.status.success() || fs::write(destination_file, output.stdout).is_err() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao descompactar o arquivo {}!", image_name)
+            ));
+        }
+    } else if let Err(_) = fs::copy(
+        &kernel.image, 
         destination_file
     ) {
         return Err(std::io::Error::new(
             std::io::ErrorKind::Other,
-            "Falha ao copiar o arquivo zImage!"
+            format!("Falha ao copiar o arquivo {}!", image_name)
         ));
     }
 
@@ -109,7 +442,7 @@ pub fn copy_boot_files(
     ).join(format!("config-{}", kernel_release));
 
     if let Err(_) = fs::copy(
-        format!("{}/.config", kernel_path), 
+        &kernel.config, 
         destination_file
     ) {
         return Err(std::io::Error::new(
@@ -118,52 +451,109 @@ pub fn copy_boot_files(
         ));
     }
 
-    // Copia o arquivo System.map
-    let destination_file = Path::new(
-        format!("{}/boot", ROOT_MOUNT_POINT).as_str()
-    ).join(format!("System.map-{}", kernel_release));
+    // Copia o arquivo System.map, quando disponível
+    if let Some(system_map) = &kernel.system_map {
+        let destination_file = Path::new(
+            format!("{}/boot", ROOT_MOUNT_POINT).as_str()
+        ).join(format!("System.map-{}", kernel_release));
 
-    if let Err(_) = fs::copy(
-        format!("{}/System.map", kernel_path), 
-        destination_file
-    ) {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "Falha ao copiar o arquivo System.map!"
-        ));
+        if let Err(_) = fs::copy(
+            system_map, 
+            destination_file
+        ) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "Falha ao copiar o arquivo System.map!"
+            ));
+        }
     }
 
     Ok(())
 }
 
+fn copy_dtb_files(
+    source_dir: &Path,
+    destination_dir: &Path
+) -> Result<usize, std::io::Error> {
+    // Copia recursivamente os arquivos .dtb, retornando quantos foram copiados
+    let entries = match fs::read_dir(source_dir) {
+        Ok(entries) => entries,
+        Err(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "Falha ao ler o diretório de origem dos arquivos DTB!"
+            ));
+        }
+    };
+
+    let mut copied = 0;
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            copied += copy_dtb_files(&path, &destination_dir.join(entry.file_name()))?;
+        } else if path.extension().is_some_and(|extension| extension == "dtb") {
+            if let Err(_) = fs::create_dir_all(destination_dir) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("Falha ao criar o diretório {}!", destination_dir.display())
+                ));
+            }
+
+            if let Err(_) = fs::copy(&path, destination_dir.join(entry.file_name())) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("Falha ao copiar o arquivo {}!", path.display())
+                ));
+            }
+
+            copied += 1;
+        }
+    }
+
+    Ok(copied)
+}
+
+fn is_gzip_file(filepath: &str) -> Result<bool, std::io::Error> {
+    // Verifica a assinatura do gzip (1f 8b) no início do arquivo
+    let contents = match fs::read(filepath) {
+        Ok(contents) => contents,
+        Err(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao ler o arquivo {}!", filepath)
+            ));
+        }
+    };
+
+    Ok(contents.starts_with(&[0x1f, 0x8b]))
+}
+
 pub fn generate_boot_images(
-    kernel_release: &str,
+    kernel: &KernelFiles,
+    boot: &BootConfig
 ) -> Result<(), std::io::Error> {
+    let kernel_release = kernel.release.as_str();
+    let load_address = boot.load_address;
+
     // Gera imagem uImage
-    let output = Command::new("mkimage")
-        .arg("-A")
-        .arg("arm")
-        .arg("-O")
-        .arg("linux")
-        .arg("-T")
-        .arg("kernel")
-        .arg("-C")
-        .arg("none")
-        .arg("-a")
-        .arg("0x600f0000")
-        .arg("-e")
-        .arg("0x600f0000")
-        .arg("-n")
-        .arg(kernel_release)
-        .arg("-d")
-        .arg(format!("{}/boot/zImage-{}", ROOT_MOUNT_POINT, kernel_release))
-        .arg(format!("{}/boot/uImage-{}", ROOT_MOUNT_POINT, kernel_release))
-        .output()?;
-    
-    if !output.status.success() {
+    let image_path = format!("{}/boot/{}-{}", ROOT_MOUNT_POINT, kernel.architecture.image_name(), kernel_release);
+    let header = UImageHeader {
+        os: ImageOs::Linux,
+        architecture: kernel.architecture.uimage_architecture(),
+        image_type: ImageType::Kernel,
+        compression: ImageCompression::None,
+        load_address,
+        entry_point: load_address,
+        timestamp: uimage_timestamp(),
+        name: kernel_release.to_string(),
+    };
+
+    if let Err(error) = write_uimage(&header, &image_path, &format!("{}/boot/uImage-{}", ROOT_MOUNT_POINT, kernel_release)) {
         return Err(std::io::Error::new(
             std::io::ErrorKind::Other,
-            "Falha ao gerar imagem uImage!"
+            format!("Falha ao gerar imagem uImage! {}", error)
         ));
     }
 
@@ -185,28 +575,25 @@ pub fn generate_boot_images(
     }
 
     // Gera imagem uInitrd
-    let output = Command::new("mkimage")
-        .arg("-A")
-        .arg("arm")
-        .arg("-O")
-        .arg("linux")
-        .arg("-T")
-        .arg("ramdisk")
-        .arg("-a")
-        .arg("0x0")
-        .arg("-e")
-        .arg("0x0")
-        .arg("-n")
-        .arg(format!("initrd.img-{}", kernel_release))
-        .arg("-d")
-        .arg(format!("{}/boot/initrd.img-{}", ROOT_MOUNT_POINT, kernel_release))
-        .arg(format!("{}/boot/uInitrd-{}", ROOT_MOUNT_POINT, kernel_release))
-        .output()?;
-    
-    if !output.status.success() {
+    let header = UImageHeader {
+        os: ImageOs::Linux,
+        architecture: kernel.architecture.uimage_architecture(),
+        image_type: ImageType::Ramdisk,
+        compression: ImageCompression::None,
+        load_address: 0,
+        entry_point: 0,
+        timestamp: uimage_timestamp(),
+        name: format!("initrd.img-{}", kernel_release),
+    };
+
+    if let Err(error) = write_uimage(
+        &header,
+        &format!("{}/boot/initrd.img-{}", ROOT_MOUNT_POINT, kernel_release),
+        &format!("{}/boot/uInitrd-{}", ROOT_MOUNT_POINT, kernel_release)
+    ) {
         return Err(std::io::Error::new(
             std::io::ErrorKind::Other,
-            "Falha ao gerar imagem uInitrd!"
+            format!("Falha ao gerar imagem uInitrd! {}", error)
         ));
     }
 
@@ -214,72 +601,263 @@ pub fn generate_boot_images(
 }
 
 pub fn create_boot_symbolic_links(
-    kernel_release: &str,
+    kernel: &KernelFiles,
     dtb_file: &str
 ) -> Result<(), std::io::Error> {
-    // Cria o link simbólico para zImage
-    let output = Command::new("chroot")
-        .arg(ROOT_MOUNT_POINT)
-        .arg("/bin/ln")
-        .arg("-s")
-        .arg(format!("/boot/zImage-{}", kernel_release))
-        .arg("/boot/zImage")
-        .output()?;
-    
-    if !output.status.success() {
+    let kernel_release = kernel.release.as_str();
+    let image_name = kernel.architecture.image_name();
+
+    // Verifica se o DTB escolhido foi copiado e é um device tree válido,
+    // pois um link para um arquivo inexistente deixa o aparelho sem boot
+    let dtb_path = format!("{}/boot/dtb-{}/{}", ROOT_MOUNT_POINT, kernel_release, dtb_file);
+
+    if fs::metadata(dtb_path.as_str()).is_err() {
         return Err(std::io::Error::new(
             std::io::ErrorKind::Other,
-            "Falha ao criar link simbólico para zImage!"
+            format!("O arquivo DTB {} não existe em /boot/dtb-{}!", dtb_file, kernel_release)
         ));
     }
 
-    // Cria o link simbólico para initrd.img
-    let output = Command::new("chroot")
-        .arg(ROOT_MOUNT_POINT)
-        .arg("/bin/ln")
-        .arg("-s")
-        .arg(format!("/boot/initrd.img-{}", kernel_release))
-        .arg("/boot/initrd.img")
-        .output()?;
+    read_device_tree(&dtb_path)?;
+
+    // Atualiza os links simbólicos, preservando os anteriores com o sufixo
+    // .old para a entrada alternativa do menu de boot
+    rotate_boot_symbolic_link(
+        image_name,
+        &format!("/boot/{}-{}", image_name, kernel_release)
+    )?;
+
+    rotate_boot_symbolic_link(
+        "initrd.img",
+        &format!("/boot/initrd.img-{}", kernel_release)
+    )?;
+
+    // Imagens legadas usadas pelos scripts de boot
+    rotate_boot_symbolic_link(
+        "uImage",
+        &format!("/boot/uImage-{}", kernel_release)
+    )?;
+
+    rotate_boot_symbolic_link(
+        "uInitrd",
+        &format!("/boot/uInitrd-{}", kernel_release)
+    )?;
+
+    rotate_boot_symbolic_link(
+        "dtb",
+        &format!("/boot/dtb-{}", kernel_release)
+    )?;
+
+    rotate_boot_symbolic_link(
+        "device_tree_binary.dtb",
+        &format!("/boot/dtb-{}/{}", kernel_release, dtb_file)
+    )?;
     
-    if !output.status.success() {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "Falha ao criar o link simbólico para initrd.img!"
-        ));
+    Ok(())
+}
+
+pub fn rotate_boot_symbolic_link(
+    link_name: &str,
+    target: &str
+) -> Result<(), std::io::Error> {
+    let link_path = format!("{}/boot/{}", ROOT_MOUNT_POINT, link_name);
+
+    // Move o destino atual para <link>.old quando ele muda
+    if let Ok(current_target) = fs::read_link(link_path.as_str()) {
+        if current_target != Path::new(target) {
+            replace_symbolic_link(&format!("{}.old", link_path), &current_target.to_string_lossy())?;
+        }
     }
 
-    // Cria o link simbólico para o diretório dtb
-    let output = Command::new("chroot")
-        .arg(ROOT_MOUNT_POINT)
-        .arg("/bin/ln")
-        .arg("-s")
-        .arg(format!("/boot/dtb-{}", kernel_release))
-        .arg("/boot/dtb")
-        .output()?;
-    
-    if !output.status.success() {
+    replace_symbolic_link(&link_path, target)
+}
+
+fn replace_symbolic_link(
+    link_path: &str,
+    target: &str
+) -> Result<(), std::io::Error> {
+    // Cria o link com um nome temporário e o renomeia sobre o antigo, de
+    // modo que o link nunca fique ausente ou pela metade
+    let temporary_link_path = format!("{}.tmp", link_path);
+    let _ = fs::remove_file(temporary_link_path.as_str());
+
+    if let Err(_) = symlink(target, temporary_link_path.as_str()) {
         return Err(std::io::Error::new(
             std::io::ErrorKind::Other,
-            "Falha ao criar o link simbólico para o diretório dtb!"
+            format!("Falha ao criar o link simbólico para {}!", target)
         ));
     }
 
-    // Cria o link simbólico para device_tree_binary.dtb
-    let output = Command::new("chroot")
-        .arg(ROOT_MOUNT_POINT)
-        .arg("/bin/ln")
-        .arg("-s")
-        .arg(format!("/boot/dtb/{}", dtb_file))
-        .arg("/boot/device_tree_binary.dtb")
-        .output()?;
-    
-    if !output.status.success() {
+    if let Err(_) = fs::rename(temporary_link_path.as_str(), link_path) {
         return Err(std::io::Error::new(
             std::io::ErrorKind::Other,
-            "Falha ao criar o link simbólico para device_tree_binary.dtb!"
+            format!("Falha ao substituir o link simbólico {}!", link_path)
         ));
     }
-    
+
     Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::golden::*;
+    use crate::soc_profiles::*;
+
+    fn boot_config(
+        soc: &str,
+        boot_format: BootFormat,
+        options: BootOptions
+    ) -> BootConfig {
+        let soc = find_soc_profile(soc).unwrap();
+
+        create_boot_config(soc.architecture, Some(soc), soc.default_dtb, boot_format, &options).unwrap()
+    }
+
+    fn console_options(
+        console: &str
+    ) -> BootOptions {
+        BootOptions {
+            command_line: CommandLineOptions {
+                console: Some(console.to_string()),
+                ..CommandLineOptions::default()
+            },
+            ..BootOptions::default()
+        }
+    }
+
+    #[test]
+    fn renders_extlinux_for_each_console() {
+        let consoles = [
+            ("extlinux-rk322x-soc-console", "rk322x", BootOptions::default()),
+            ("extlinux-s905x-soc-console", "s905x", BootOptions::default()),
+            ("extlinux-h3-custom-console", "h3", console_options("ttyS1,9600n8")),
+            ("extlinux-h6-no-console", "h6", console_options("none")),
+        ];
+
+        for (name, soc, options) in consoles {
+            let boot = boot_config(soc, BootFormat::Legacy, options);
+
+            assert_golden(name, &render_extlinux_configuration("/dev/mmcblk2p1", &boot, &InstalledBootFiles::default()));
+        }
+    }
+
+    #[test]
+    fn renders_extlinux_with_kernel_args_and_timeout() {
+        let options = BootOptions {
+            command_line: CommandLineOptions {
+                console: None,
+                loglevel: Some(7),
+                extra_args: vec!["rootfstype=btrfs".to_string(), "quiet".to_string()],
+                removed_args: vec!["earlyprintk".to_string()],
+            },
+            timeout: 10,
+            ..BootOptions::default()
+        };
+        let boot = boot_config("h3", BootFormat::Legacy, options);
+
+        assert_golden(
+            "extlinux-kernel-args",
+            &render_extlinux_configuration("/dev/mmcblk0p1", &boot, &InstalledBootFiles::default())
+        );
+    }
+
+    #[test]
+    fn keeps_serial_console_with_extra_consoles() {
+        let options = BootOptions {
+            command_line: CommandLineOptions {
+                extra_args: vec!["console=tty1".to_string(), "console=ttyS2,1500000".to_string()],
+                ..CommandLineOptions::default()
+            },
+            ..BootOptions::default()
+        };
+        let boot = boot_config("rk322x", BootFormat::Legacy, options);
+        let command_line = kernel_command_line("/dev/mmcblk2p1", &boot);
+
+        assert!(command_line.ends_with(" console=ttyS2,1500000 earlycon=uart8250,mmio32,0x11030000 console=tty1"));
+    }
+
+    #[test]
+    fn renders_extlinux_with_installed_kernels() {
+        let boot = boot_config("rk322x", BootFormat::Legacy, BootOptions::default());
+        let installed = InstalledBootFiles {
+            fallback: true,
+            fit_images: Vec::new(),
+            kernel_releases: vec!["6.1.0-rk322x".to_string(), "6.6.0-rk322x".to_string()],
+        };
+
+        assert_golden("extlinux-installed-kernels", &render_extlinux_configuration("/dev/mmcblk2p1", &boot, &installed));
+    }
+
+    #[test]
+    fn renders_extlinux_with_fit_images() {
+        let boot = boot_config("rk3328", BootFormat::Fit, BootOptions::default());
+
+        // O kernel 6.1.0 foi instalado antes do FIT e continua com os
+        // arquivos separados
+        let installed = InstalledBootFiles {
+            fallback: true,
+            fit_images: vec!["image.itb".to_string(), "image-6.6.0-rk3328.itb".to_string()],
+            kernel_releases: vec!["6.1.0-rk3328".to_string(), "6.6.0-rk3328".to_string()],
+        };
+
+        assert_golden("extlinux-fit", &render_extlinux_configuration("/dev/mmcblk2p1", &boot, &installed));
+    }
+
+    #[test]
+    fn renders_extlinux_with_overlays() {
+        for (name, merge_overlays) in [("extlinux-overlays", false), ("extlinux-merged-overlays", true)] {
+            let options = BootOptions {
+                overlays: vec!["rk322x-emmc".to_string(), "rk322x-led-conf1.dtbo".to_string()],
+                merge_overlays,
+                ..BootOptions::default()
+            };
+            let boot = boot_config("rk322x", BootFormat::Legacy, options);
+            let installed = InstalledBootFiles {
+                kernel_releases: vec!["6.6.0-rk322x".to_string()],
+                ..InstalledBootFiles::default()
+            };
+
+            assert_golden(name, &render_extlinux_configuration("/dev/mmcblk2p1", &boot, &installed));
+        }
+    }
+
+    #[test]
+    fn sorts_kernel_releases_by_version() {
+        let mut releases = vec!["6.10.0-rk322x", "6.9.12-rk322x", "6.1.0-rk322x", "6.9.2-rk322x", "6.10.0"];
+        releases.sort_by(|a, b| compare_kernel_releases(a, b));
+
+        assert_eq!(releases, vec!["6.1.0-rk322x", "6.9.2-rk322x", "6.9.12-rk322x", "6.10.0", "6.10.0-rk322x"]);
+    }
+
+    #[test]
+    fn reads_installed_boot_files_from_target_root() {
+        let target_root = std::env::temp_dir().join(format!("installer-extlinux-{}", std::process::id()));
+        let boot_dir = target_root.join("boot");
+        let boot = boot_config("rk3328", BootFormat::Fit, BootOptions::default());
+
+        fs::create_dir_all(&boot_dir).unwrap();
+        fs::write(boot_dir.join("Image-6.1.0-rk3328"), "").unwrap();
+        fs::write(boot_dir.join("Image-6.6.0-rk3328"), "").unwrap();
+        fs::write(boot_dir.join("image-6.6.0-rk3328.itb"), "").unwrap();
+        symlink("/boot/Image-6.6.0-rk3328", boot_dir.join("Image")).unwrap();
+        symlink("/boot/Image-6.1.0-rk3328", boot_dir.join("Image.old")).unwrap();
+        symlink("/boot/image-6.6.0-rk3328.itb", boot_dir.join("image.itb")).unwrap();
+
+        let installed = read_installed_boot_files(target_root.to_str().unwrap(), &boot).unwrap();
+
+        assert!(installed.fallback);
+        assert_eq!(installed.fit_images, vec!["image.itb", "image-6.6.0-rk3328.itb"]);
+        assert_eq!(installed.kernel_releases, vec!["6.1.0-rk3328", "6.6.0-rk3328"]);
+
+        create_extlinux_configuration_file(target_root.to_str().unwrap(), "/dev/mmcblk2p1", &boot).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(boot_dir.join("extlinux/extlinux.conf")).unwrap(),
+            render_extlinux_configuration("/dev/mmcblk2p1", &boot, &installed)
+        );
+
+        fs::remove_dir_all(target_root).unwrap();
+    }
 }
\ No newline at end of file
@@ -4,7 +4,8 @@ use crate::constants::*;
 
 pub fn configure_storage(
     storage_device_path: &str,
-    root_partition_path: &str
+    root_partition_path: &str,
+    first_sector: Option<u64>
 ) -> Result<(), std::io::Error> {
     // Executa o comando para criar uma tabela de partição MBR
     let output = Command::new("parted")
@@ -21,14 +22,20 @@ pub fn configure_storage(
         ));
     }
     
-    // Executa o comando para criar uma partição raiz
+    // Executa o comando para criar uma partição raiz, depois da área do
+    // carregador quando houver uma
+    let start = match first_sector {
+        Some(first_sector) => format!("{}s", first_sector),
+        None => "0%".to_string(),
+    };
+
     let output = Command::new("parted")
         .arg("--script")
         .arg(storage_device_path)
         .arg("mkpart")
         .arg("primary")
         .arg("ext4")
-        .arg("0%")
+        .arg(start)
         .arg("100%")
         .output()?;
     
@@ -58,7 +65,7 @@ pub fn configure_storage(
     
     // Formata a partição raiz com EXT4
     let output = Command::new("mkfs.ext4")
-        .arg(&root_partition_path)
+        .arg(root_partition_path)
         .output()?;
     
     if !output.status.success() {
@@ -83,7 +90,7 @@ pub fn configure_storage(
 
     // Monta a partição raiz
     let output = Command::new("mount")
-        .arg(&root_partition_path)
+        .arg(root_partition_path)
         .arg(ROOT_MOUNT_POINT)
         .output()?;
     
@@ -1 +1,37 @@
-pub const ROOT_MOUNT_POINT: &str = "/mnt/system";
\ No newline at end of file
+pub const ROOT_MOUNT_POINT: &str = "/mnt/system";
+
+pub const DEFAULT_ARCHITECTURE: &str = "armhf";
+pub const DEFAULT_MIRROR: &str = "http://deb.debian.org/debian";
+pub const DEFAULT_TARGET_MIRROR: &str = "http://debian.c3sl.ufpr.br/debian";
+pub const DEFAULT_SUITE: &str = "bookworm";
+
+pub const DEFAULT_BOOT_TIMEOUT: u32 = 3;
+pub const MAX_BOOT_TIMEOUT: u32 = 600;
+pub const DEFAULT_KERNEL_LOGLEVEL: u32 = 0;
+pub const DEFAULT_TEST_BOOT_TIMEOUT: u32 = 300;
+pub const DEFAULT_TEST_BOOT_MEMORY: u32 = 1024;
+pub const DEFAULT_TEST_BOOT_MARKER: &str = "login:";
+pub const TEST_BOOT_LOG_LINES: usize = 50;
+pub const AUTO_DTB_FILE: &str = "auto";
+
+pub const KERNEL_STAGING_DIR: &str = "/tmp/installer-kernel";
+
+pub const OFFLINE_REPO_CHROOT_DIR: &str = "/mnt/installer-offline";
+pub const OFFLINE_SOURCE_LIST: &str = "/tmp/installer-offline.list";
+
+pub const APT_PROXY_CONF: &str = "01installer-proxy";
+pub const EXTRA_SOURCES_LIST: &str = "installer-extra.list";
+pub const KERNEL_HOOK_NAME: &str = "zz-installer-uboot";
+pub const BOARD_LEDS_CONF: &str = "installer-leds.conf";
+pub const EXTLINUX_BACKUP: &str = "/boot/extlinux/extlinux.conf.bak";
+pub const BOOT_PROFILE: &str = "/etc/installer/boot.profile";
+pub const SAVED_OVERLAYS_DIR: &str = "/etc/installer/overlays";
+pub const SERIAL_GETTY_DROPIN: &str = "installer-console.conf";
+
+pub const DEPENDENCY_PACKAGES: &[&str] = &[
+    "parted",
+    "e2fsprogs",
+    "debootstrap",
+    "mmdebstrap",
+    "openssl",
+];
\ No newline at end of file
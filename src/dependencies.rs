@@ -1,8 +1,14 @@
 use std::process::Command;
 
-pub fn install_dependencies() -> Result<(), std::io::Error> {
+use crate::constants::*;
+
+pub fn install_dependencies(
+    apt_options: &[String],
+    extra_dependencies: &[&str]
+) -> Result<(), std::io::Error> {
     // Atualiza o banco de dados do gerenciador de pacotes
     let output = Command::new("apt")
+        .args(apt_options)
         .arg("update")
         .output()?;
     
@@ -15,12 +21,10 @@ pub fn install_dependencies() -> Result<(), std::io::Error> {
 
     // Instala as dependências do instalador
     let output = Command::new("apt")
+        .args(apt_options)
         .arg("install")
-        .arg("parted")
-        .arg("e2fsprogs")
-        .arg("debootstrap")
-        .arg("openssl")
-        .arg("build-essential")
+        .args(DEPENDENCY_PACKAGES)
+        .args(extra_dependencies)
         .arg("-y")
         .output()?;
     
@@ -0,0 +1,380 @@
+use std::fs;
+
+// Formato do flattened device tree (especificação do devicetree, capítulo 5)
+const FDT_MAGIC: u32 = 0xd00dfeed;
+const FDT_HEADER_SIZE: usize = 40;
+const FDT_MIN_VERSION: u32 = 16;
+const FDT_LAST_COMPATIBLE_VERSION: u32 = 16;
+const FDT_BEGIN_NODE: u32 = 0x1;
+const FDT_END_NODE: u32 = 0x2;
+const FDT_PROP: u32 = 0x3;
+const FDT_NOP: u32 = 0x4;
+const FDT_END: u32 = 0x9;
+
+pub struct DeviceTree {
+    pub model: Option<String>,
+    pub compatible: Vec<String>,
+    pub version: u32,
+    pub total_size: usize,
+    pub node_count: usize,
+    pub property_count: usize,
+}
+
+pub fn read_device_tree(
+    filepath: &str
+) -> Result<DeviceTree, std::io::Error> {
+    // Lê e valida um arquivo DTB
+    let contents = match fs::read(filepath) {
+        Ok(contents) => contents,
+        Err(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao ler o arquivo {}!", filepath)
+            ));
+        }
+    };
+
+    match parse_device_tree(&contents) {
+        Ok(device_tree) => Ok(device_tree),
+        Err(error) => Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("{} ({})", error, filepath)
+        )),
+    }
+}
+
+pub fn parse_device_tree(
+    contents: &[u8]
+) -> Result<DeviceTree, std::io::Error> {
+    // Valida o cabeçalho
+    if contents.len() < FDT_HEADER_SIZE || read_u32(contents, 0) != Some(FDT_MAGIC) {
+        return Err(invalid("O arquivo não é um device tree válido!"));
+    }
+
+    let total_size = read_u32(contents, 4).unwrap_or(0) as usize;
+    let struct_offset = read_u32(contents, 8).unwrap_or(0) as usize;
+    let strings_offset = read_u32(contents, 12).unwrap_or(0) as usize;
+    let version = read_u32(contents, 20).unwrap_or(0);
+    let last_compatible_version = read_u32(contents, 24).unwrap_or(0);
+    let strings_size = read_u32(contents, 32).unwrap_or(0) as usize;
+    let struct_size = read_u32(contents, 36).unwrap_or(0) as usize;
+
+    if total_size > contents.len() || struct_offset > total_size {
+        return Err(invalid("O device tree está truncado!"));
+    }
+
+    // O tamanho do bloco de estrutura só existe no cabeçalho a partir da versão 17
+    let struct_size = if version >= 17 { struct_size } else { total_size - struct_offset };
+
+    // O U-Boot e o kernel leem a versão 17, compatível com a 16
+    if version < FDT_MIN_VERSION || last_compatible_version > FDT_LAST_COMPATIBLE_VERSION {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Versão do device tree não suportada: {}!", version)
+        ));
+    }
+
+    if struct_offset + struct_size > total_size || strings_offset + strings_size > total_size {
+        return Err(invalid("O cabeçalho do device tree é inválido!"));
+    }
+
+    let structure = &contents[..struct_offset + struct_size];
+    let strings = &contents[strings_offset..strings_offset + strings_size];
+
+    // Percorre o bloco de estrutura, conferindo o aninhamento dos nós. As
+    // propriedades do nó raiz identificam o aparelho
+    let mut device_tree = DeviceTree {
+        model: None,
+        compatible: Vec::new(),
+        version,
+        total_size,
+        node_count: 0,
+        property_count: 0,
+    };
+    let mut offset = struct_offset;
+    let mut depth = 0;
+
+    loop {
+        match read_u32(structure, offset) {
+            Some(FDT_BEGIN_NODE) => {
+                let name = c_string(structure, offset + 4)?;
+
+                if depth == 0 && (device_tree.node_count > 0 || !name.is_empty()) {
+                    return Err(invalid("O device tree não começa pelo nó raiz!"));
+                }
+
+                depth += 1;
+                device_tree.node_count += 1;
+                offset = align(offset + 4 + name.len() + 1);
+            },
+            Some(FDT_END_NODE) => {
+                if depth == 0 {
+                    return Err(invalid("Fim de nó inesperado no device tree!"));
+                }
+
+                depth -= 1;
+                offset += 4;
+            },
+            Some(FDT_PROP) => {
+                if depth == 0 {
+                    return Err(invalid("Propriedade fora de um nó no device tree!"));
+                }
+
+                let length = read_u32(structure, offset + 4).ok_or_else(truncated)? as usize;
+                let name_offset = read_u32(structure, offset + 8).ok_or_else(truncated)? as usize;
+                let value = structure.get(offset + 12..offset + 12 + length).ok_or_else(truncated)?;
+                let name = c_string(strings, name_offset)?;
+
+                if depth == 1 {
+                    match name {
+                        "model" => device_tree.model = string_list(value).into_iter().next(),
+                        "compatible" => device_tree.compatible = string_list(value),
+                        _ => {},
+                    }
+                }
+
+                device_tree.property_count += 1;
+                offset = align(offset + 12 + length);
+            },
+            Some(FDT_NOP) => offset += 4,
+            Some(FDT_END) => break,
+            Some(_) => return Err(invalid("Token desconhecido no device tree!")),
+            None => return Err(truncated()),
+        }
+    }
+
+    if depth != 0 || device_tree.node_count == 0 {
+        return Err(invalid("Os nós do device tree não estão balanceados!"));
+    }
+
+    Ok(device_tree)
+}
+
+pub fn string_list(value: &[u8]) -> Vec<String> {
+    // Propriedades como compatible são listas de strings terminadas em zero
+    value
+        .split(|&byte| byte == 0)
+        .filter(|item| !item.is_empty())
+        .map(|item| String::from_utf8_lossy(item).to_string())
+        .collect()
+}
+
+fn c_string(
+    bytes: &[u8],
+    offset: usize
+) -> Result<&str, std::io::Error> {
+    let tail = bytes.get(offset..).ok_or_else(truncated)?;
+    let length = tail.iter().position(|&byte| byte == 0).ok_or_else(truncated)?;
+
+    match std::str::from_utf8(&tail[..length]) {
+        Ok(value) => Ok(value),
+        Err(_) => Err(invalid("Nome inválido no device tree!")),
+    }
+}
+
+fn read_u32(
+    bytes: &[u8],
+    offset: usize
+) -> Option<u32> {
+    let value = bytes.get(offset..offset + 4)?;
+
+    Some(u32::from_be_bytes([value[0], value[1], value[2], value[3]]))
+}
+
+fn align(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+fn invalid(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::Other, message)
+}
+
+fn truncated() -> std::io::Error {
+    invalid("O device tree está truncado!")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Nomes das propriedades no bloco de strings: "compatible" no offset 0,
+    // "model" no 11 e "#address-cells" no 17
+    const STRINGS: &[u8] = b"compatible\0model\0#address-cells\0";
+
+    fn begin_node(name: &str) -> Vec<u8> {
+        let mut token = FDT_BEGIN_NODE.to_be_bytes().to_vec();
+        token.extend_from_slice(name.as_bytes());
+        token.push(0);
+        token.resize(align(token.len()), 0);
+        token
+    }
+
+    fn property(name_offset: u32, value: &[u8]) -> Vec<u8> {
+        let mut token = FDT_PROP.to_be_bytes().to_vec();
+        token.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        token.extend_from_slice(&name_offset.to_be_bytes());
+        token.extend_from_slice(value);
+        token.resize(align(token.len()), 0);
+        token
+    }
+
+    fn token(value: u32) -> Vec<u8> {
+        value.to_be_bytes().to_vec()
+    }
+
+    // Nó raiz com compatible e model e um nó filho /cpus
+    fn board_structure() -> Vec<u8> {
+        [
+            begin_node(""),
+            property(0, b"vendor,board\0vendor,soc\0"),
+            property(11, b"Vendor Board\0"),
+            token(FDT_NOP),
+            begin_node("cpus"),
+            property(17, &1u32.to_be_bytes()),
+            token(FDT_END_NODE),
+            token(FDT_END_NODE),
+            token(FDT_END),
+        ].concat()
+    }
+
+    fn build_device_tree(
+        structure: &[u8],
+        version: u32,
+        last_compatible_version: u32
+    ) -> Vec<u8> {
+        // Cabeçalho, mapa de memória reservada vazio, estrutura e strings
+        let struct_offset = FDT_HEADER_SIZE + 16;
+        let strings_offset = struct_offset + structure.len();
+        let total_size = strings_offset + STRINGS.len();
+
+        let header = [
+            FDT_MAGIC,
+            total_size as u32,
+            struct_offset as u32,
+            strings_offset as u32,
+            FDT_HEADER_SIZE as u32,
+            version,
+            last_compatible_version,
+            0,
+            STRINGS.len() as u32,
+            structure.len() as u32,
+        ];
+
+        let mut contents: Vec<u8> = header.iter().flat_map(|value| value.to_be_bytes()).collect();
+        contents.extend_from_slice(&[0; 16]);
+        contents.extend_from_slice(structure);
+        contents.extend_from_slice(STRINGS);
+        contents
+    }
+
+    fn parse_error(contents: &[u8]) -> String {
+        match parse_device_tree(contents) {
+            Ok(_) => panic!("O device tree deveria ser rejeitado!"),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_board_device_tree() {
+        let contents = build_device_tree(&board_structure(), 17, 16);
+        let device_tree = parse_device_tree(&contents).unwrap();
+
+        assert_eq!(device_tree.model.as_deref(), Some("Vendor Board"));
+        assert_eq!(device_tree.compatible, vec!["vendor,board", "vendor,soc"]);
+        assert_eq!(device_tree.version, 17);
+        assert_eq!(device_tree.total_size, contents.len());
+        assert_eq!(device_tree.node_count, 2);
+        assert_eq!(device_tree.property_count, 3);
+    }
+
+    #[test]
+    fn parses_version_16_without_struct_size() {
+        let mut contents = build_device_tree(&board_structure(), 16, 16);
+        contents[36..40].copy_from_slice(&[0; 4]);
+
+        assert_eq!(parse_device_tree(&contents).unwrap().node_count, 2);
+    }
+
+    #[test]
+    fn rejects_invalid_header() {
+        let contents = build_device_tree(&board_structure(), 17, 16);
+
+        let mut bad_magic = contents.clone();
+        bad_magic[0] = 0;
+        assert_eq!(parse_error(&bad_magic), "O arquivo não é um device tree válido!");
+        assert_eq!(parse_error(&contents[..FDT_HEADER_SIZE - 1]), "O arquivo não é um device tree válido!");
+
+        let mut bad_strings = contents.clone();
+        bad_strings[32..36].copy_from_slice(&0x1000u32.to_be_bytes());
+        assert_eq!(parse_error(&bad_strings), "O cabeçalho do device tree é inválido!");
+    }
+
+    #[test]
+    fn rejects_unsupported_versions() {
+        assert_eq!(parse_error(&build_device_tree(&board_structure(), 15, 15)), "Versão do device tree não suportada: 15!");
+        assert_eq!(parse_error(&build_device_tree(&board_structure(), 18, 17)), "Versão do device tree não suportada: 18!");
+    }
+
+    #[test]
+    fn rejects_truncated_device_tree() {
+        let contents = build_device_tree(&board_structure(), 17, 16);
+        assert_eq!(parse_error(&contents[..contents.len() - 1]), "O device tree está truncado!");
+
+        // Estrutura sem o token FDT_END
+        let structure = board_structure();
+        let contents = build_device_tree(&structure[..structure.len() - 4], 17, 16);
+        assert_eq!(parse_error(&contents), "O device tree está truncado!");
+
+        // Propriedade maior que o bloco de estrutura
+        let structure = [begin_node(""), token(FDT_PROP), token(64), token(0)].concat();
+        assert_eq!(parse_error(&build_device_tree(&structure, 17, 16)), "O device tree está truncado!");
+
+        // Nome da propriedade fora do bloco de strings
+        let structure = [begin_node(""), property(100, b"x\0"), token(FDT_END_NODE), token(FDT_END)].concat();
+        assert_eq!(parse_error(&build_device_tree(&structure, 17, 16)), "O device tree está truncado!");
+    }
+
+    #[test]
+    fn rejects_corrupted_structure() {
+        let cases = [
+            (
+                [begin_node(""), begin_node("cpus"), token(FDT_END_NODE), token(FDT_END)].concat(),
+                "Os nós do device tree não estão balanceados!",
+            ),
+            (
+                token(FDT_END),
+                "Os nós do device tree não estão balanceados!",
+            ),
+            (
+                [begin_node(""), token(FDT_END_NODE), token(FDT_END_NODE), token(FDT_END)].concat(),
+                "Fim de nó inesperado no device tree!",
+            ),
+            (
+                [property(11, b"x\0"), token(FDT_END)].concat(),
+                "Propriedade fora de um nó no device tree!",
+            ),
+            (
+                [begin_node("raiz"), token(FDT_END_NODE), token(FDT_END)].concat(),
+                "O device tree não começa pelo nó raiz!",
+            ),
+            (
+                [begin_node(""), token(FDT_END_NODE), begin_node(""), token(FDT_END_NODE), token(FDT_END)].concat(),
+                "O device tree não começa pelo nó raiz!",
+            ),
+            (
+                [begin_node(""), token(0x7), token(FDT_END_NODE), token(FDT_END)].concat(),
+                "Token desconhecido no device tree!",
+            ),
+        ];
+
+        for (structure, message) in cases {
+            assert_eq!(parse_error(&build_device_tree(&structure, 17, 16)), message);
+        }
+    }
+
+    #[test]
+    fn splits_string_lists() {
+        assert_eq!(string_list(b"a,b\0c\0\0"), vec!["a,b", "c"]);
+        assert!(string_list(b"").is_empty());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,170 @@
+use std::fs;
+use std::process::Command;
+
+use crate::configure_boot::*;
+use crate::constants::*;
+use crate::kernel::*;
+
+pub fn fit_configuration_name(dtb_file: &str) -> String {
+    // Nome da configuração do FIT correspondente a um DTB
+    let filename = dtb_file.rsplit('/').next().unwrap_or(dtb_file);
+
+    format!("conf-{}", filename.trim_end_matches(".dtb"))
+}
+
+pub fn create_fit_image(
+    kernel: &KernelFiles,
+    boot: &BootConfig
+) -> Result<(), std::io::Error> {
+    let kernel_release = kernel.release.as_str();
+    let architecture = kernel.architecture.name();
+    let load_address = boot.load_address;
+    let dtb_file = boot.dtb_file.as_str();
+
+    // Inclui os DTBs da mesma família do DTB escolhido (mesmo diretório e
+    // mesmo prefixo), com uma configuração para cada um
+    let dtb_files = fit_dtb_files(kernel_release, dtb_file)?;
+
+    // Cria o arquivo /boot/image-<kernel_release>.its, com caminhos relativos
+    // a /boot para que possa ser reutilizado no próprio aparelho
+    let mut its = String::new();
+
+    its += "/dts-v1/;\n\n";
+    its += "/ {\n";
+    its += format!("    description = \"Debian GNU/Linux, Linux {}\";\n", kernel_release).as_str();
+    its += "    #address-cells = <1>;\n\n";
+    its += "    images {\n";
+    its += "        kernel {\n";
+    its += format!("            description = \"Linux {}\";\n", kernel_release).as_str();
+    its += format!("            data = /incbin/(\"{}-{}\");\n", kernel.architecture.image_name(), kernel_release).as_str();
+    its += "            type = \"kernel\";\n";
+    its += format!("            arch = \"{}\";\n", architecture).as_str();
+    its += "            os = \"linux\";\n";
+    its += "            compression = \"none\";\n";
+    its += format!("            load = <{:#010x}>;\n", load_address).as_str();
+    its += format!("            entry = <{:#010x}>;\n", load_address).as_str();
+    its += "            hash-1 {\n                algo = \"crc32\";\n            };\n";
+    its += "        };\n\n";
+    its += "        ramdisk {\n";
+    its += format!("            description = \"initrd.img-{}\";\n", kernel_release).as_str();
+    its += format!("            data = /incbin/(\"initrd.img-{}\");\n", kernel_release).as_str();
+    its += "            type = \"ramdisk\";\n";
+    its += format!("            arch = \"{}\";\n", architecture).as_str();
+    its += "            os = \"linux\";\n";
+    its += "            compression = \"none\";\n";
+    its += "            hash-1 {\n                algo = \"crc32\";\n            };\n";
+    its += "        };\n";
+
+    for dtb in &dtb_files {
+        let name = fit_configuration_name(dtb).replacen("conf-", "fdt-", 1);
+
+        its += format!("\n        {} {{\n", name).as_str();
+        its += format!("            description = \"{}\";\n", dtb).as_str();
+        its += format!("            data = /incbin/(\"dtb-{}/{}\");\n", kernel_release, dtb).as_str();
+        its += "            type = \"flat_dt\";\n";
+        its += format!("            arch = \"{}\";\n", architecture).as_str();
+        its += "            compression = \"none\";\n";
+        its += "            hash-1 {\n                algo = \"crc32\";\n            };\n";
+        its += "        };\n";
+    }
+
+    its += "    };\n\n";
+    its += "    configurations {\n";
+    its += format!("        default = \"{}\";\n", fit_configuration_name(dtb_file)).as_str();
+
+    for dtb in &dtb_files {
+        let name = fit_configuration_name(dtb);
+
+        its += format!("\n        {} {{\n", name).as_str();
+        its += format!("            description = \"{}\";\n", dtb).as_str();
+        its += "            kernel = \"kernel\";\n";
+        its += "            ramdisk = \"ramdisk\";\n";
+        its += format!("            fdt = \"{}\";\n", name.replacen("conf-", "fdt-", 1)).as_str();
+        its += "        };\n";
+    }
+
+    its += "    };\n";
+    its += "};\n";
+
+    let its_path = format!("{}/boot/image-{}.its", ROOT_MOUNT_POINT, kernel_release);
+
+    if let Err(_) = fs::write(its_path.as_str(), its) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao criar o arquivo /boot/image-{}.its!", kernel_release)
+        ));
+    }
+
+    // Gera a imagem /boot/image-<kernel_release>.itb
+    let output = Command::new("mkimage")
+        .arg("-f")
+        .arg(its_path.as_str())
+        .arg(format!("{}/boot/image-{}.itb", ROOT_MOUNT_POINT, kernel_release))
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao gerar a imagem FIT! {}", String::from_utf8_lossy(&output.stderr).trim())
+        ));
+    }
+
+    rotate_boot_symbolic_link(
+        "image.itb",
+        &format!("/boot/image-{}.itb", kernel_release)
+    )?;
+
+    Ok(())
+}
+
+fn fit_dtb_files(
+    kernel_release: &str,
+    dtb_file: &str
+) -> Result<Vec<String>, std::io::Error> {
+    // Lista os DTBs com o mesmo prefixo do escolhido (ex.: rk3328-*), para
+    // não incluir no FIT os DTBs de todos os SoCs suportados pelo kernel
+    let (subdirectory, filename) = match dtb_file.rsplit_once('/') {
+        Some((subdirectory, filename)) => (Some(subdirectory), filename),
+        None => (None, dtb_file),
+    };
+    let family = filename.split('-').next().unwrap_or(filename);
+
+    let dtb_dir = match subdirectory {
+        Some(subdirectory) => format!("{}/boot/dtb-{}/{}", ROOT_MOUNT_POINT, kernel_release, subdirectory),
+        None => format!("{}/boot/dtb-{}", ROOT_MOUNT_POINT, kernel_release),
+    };
+
+    let entries = match fs::read_dir(dtb_dir.as_str()) {
+        Ok(entries) => entries,
+        Err(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao ler o diretório {}!", dtb_dir)
+            ));
+        }
+    };
+
+    let mut dtb_files: Vec<String> = Vec::new();
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        if name.ends_with(".dtb") && name.split('-').next() == Some(family) {
+            dtb_files.push(match subdirectory {
+                Some(subdirectory) => format!("{}/{}", subdirectory, name),
+                None => name,
+            });
+        }
+    }
+
+    if !dtb_files.iter().any(|dtb| dtb == dtb_file) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("O arquivo DTB {} não foi encontrado no kernel {}!", dtb_file, kernel_release)
+        ));
+    }
+
+    dtb_files.sort();
+
+    Ok(dtb_files)
+}
\ No newline at end of file
@@ -0,0 +1,25 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+// Compara o texto gerado com o arquivo de referência em tests/golden. Com
+// UPDATE_GOLDEN=1, os arquivos de referência são regravados
+pub fn assert_golden(
+    name: &str,
+    actual: &str
+) {
+    let filepath = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(name);
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(filepath.parent().unwrap()).unwrap();
+        fs::write(&filepath, actual).unwrap();
+        return;
+    }
+
+    let expected = match fs::read_to_string(&filepath) {
+        Ok(expected) => expected,
+        Err(_) => panic!("Arquivo de referência {} não encontrado! Gere com UPDATE_GOLDEN=1.", filepath.display()),
+    };
+
+    assert_eq!(actual, expected, "O conteúdo gerado difere de {}", filepath.display());
+}
\ No newline at end of file
@@ -1,15 +1,181 @@
+use std::fs;
+use std::path::Path;
 use std::process::Command;
 
 use crate::constants::*;
+use crate::kernel::*;
+use crate::package_sets::*;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum RootfsBackend {
+    Debootstrap,
+    Mmdebstrap,
+}
+
+impl RootfsBackend {
+    pub fn from_name(name: &str) -> Result<RootfsBackend, std::io::Error> {
+        match name {
+            "debootstrap" => Ok(RootfsBackend::Debootstrap),
+            "mmdebstrap" => Ok(RootfsBackend::Mmdebstrap),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Ferramenta de criação da raiz desconhecida: {}!", name)
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RootfsBackend::Debootstrap => "debootstrap",
+            RootfsBackend::Mmdebstrap => "mmdebstrap",
+        }
+    }
+}
+
+pub struct RootfsHook {
+    pub phase: String,
+    pub command: String,
+}
+
+impl RootfsHook {
+    pub fn parse(value: &str) -> Result<RootfsHook, std::io::Error> {
+        // Interpreta um hook no formato <fase>:<comando>
+        if let Some((phase, command)) = value.split_once(':') {
+            if ["setup", "extract", "essential", "customize"].contains(&phase) {
+                return Ok(RootfsHook {
+                    phase: phase.to_string(),
+                    command: command.to_string(),
+                });
+            }
+        }
+
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Hook inválido: {}!", value)
+        ))
+    }
+}
+
+pub struct RootfsOptions {
+    pub backend: RootfsBackend,
+    pub suite: String,
+    pub architecture: Option<String>,
+    pub mirrors: Vec<String>,
+    pub hooks: Vec<RootfsHook>,
+    pub offline_repo: Option<String>,
+    pub proxy: Option<String>,
+    pub packages: PackageSelection,
+    pub include_packages: bool,
+}
+
+impl Default for RootfsOptions {
+    fn default() -> Self {
+        RootfsOptions {
+            backend: RootfsBackend::Debootstrap,
+            suite: DEFAULT_SUITE.to_string(),
+            architecture: None,
+            mirrors: Vec::new(),
+            hooks: Vec::new(),
+            offline_repo: None,
+            proxy: None,
+            packages: PackageSelection::default(),
+            include_packages: false,
+        }
+    }
+}
+
+impl RootfsOptions {
+    pub fn mirrors(&self) -> Vec<String> {
+        // Usa o repositório local no modo offline e o espelho padrão quando
+        // nenhum foi informado
+        if let Some(offline_repo) = &self.offline_repo {
+            vec![format!("file://{}", offline_repo)]
+        } else if self.mirrors.is_empty() {
+            vec![DEFAULT_MIRROR.to_string()]
+        } else {
+            self.mirrors.clone()
+        }
+    }
+
+    pub fn target_mirror(&self) -> String {
+        // Espelho gravado no sources.list do novo sistema, que não pode
+        // apontar para o repositório local do modo offline
+        match self.mirrors.first() {
+            Some(mirror) if self.offline_repo.is_none() => mirror.clone(),
+            _ => DEFAULT_TARGET_MIRROR.to_string(),
+        }
+    }
+
+    pub fn architecture(&self) -> &str {
+        // Usa a arquitetura padrão quando nenhuma foi informada nem detectada
+        self.architecture.as_deref().unwrap_or(DEFAULT_ARCHITECTURE)
+    }
+
+    pub fn proxy_apt_options(&self) -> Vec<String> {
+        // Opções passadas ao APT para usar o proxy durante a instalação
+        match &self.proxy {
+            Some(proxy) => vec![
+                "-o".to_string(),
+                format!("Acquire::http::Proxy={}", proxy),
+            ],
+            None => Vec::new(),
+        }
+    }
+}
+
+pub fn create_root_filesystem(
+    rootfs: &RootfsOptions,
+    target: &str
+) -> Result<(), std::io::Error> {
+    // Cria o sistema de arquivos da raiz com a ferramenta escolhida
+    match rootfs.backend {
+        RootfsBackend::Debootstrap => debootstrap_first_stage(rootfs, target),
+        RootfsBackend::Mmdebstrap => mmdebstrap(rootfs, target),
+    }
+}
+
+pub fn prepare_root_filesystem(
+    rootfs: &RootfsOptions
+) -> Result<(), std::io::Error> {
+    // O mmdebstrap entrega a raiz pronta, sem segundo estágio
+    match rootfs.backend {
+        RootfsBackend::Debootstrap => debootstrap_second_stage(),
+        RootfsBackend::Mmdebstrap => Ok(()),
+    }
+}
+
+fn debootstrap_first_stage(
+    rootfs: &RootfsOptions,
+    target: &str
+) -> Result<(), std::io::Error> {
+    if !rootfs.hooks.is_empty() || rootfs.mirrors.len() > 1 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "O debootstrap não suporta hooks nem múltiplos espelhos!"
+        ));
+    }
 
-pub fn create_root_filesystem() -> Result<(), std::io::Error> {
     // Cria o sistema de arquivos da raiz
-    let output = Command::new("debootstrap")
-        .arg("--arch=armhf")
-        .arg("--foreign")
-        .arg("bookworm")
-        .arg(ROOT_MOUNT_POINT)
-        .arg("http://deb.debian.org/debian")
+    let mut command = Command::new("debootstrap");
+
+    command
+        .arg(format!("--arch={}", rootfs.architecture()))
+        .arg("--foreign");
+
+    // O repositório local não é assinado
+    if rootfs.offline_repo.is_some() {
+        command.arg("--no-check-gpg");
+    }
+
+    // O debootstrap usa o proxy da variável de ambiente
+    if let Some(proxy) = &rootfs.proxy {
+        command.env("http_proxy", proxy);
+    }
+
+    let output = command
+        .arg(&rootfs.suite)
+        .arg(target)
+        .arg(&rootfs.mirrors()[0])
         .output()?;
     
     if !output.status.success() {
@@ -22,7 +188,7 @@ pub fn create_root_filesystem() -> Result<(), std::io::Error> {
     Ok(())
 }
 
-pub fn prepare_root_filesystem() -> Result<(), std::io::Error> {
+fn debootstrap_second_stage() -> Result<(), std::io::Error> {
     // Prepara o sistema de arquivos da raiz
     let output = Command::new("chroot")
         .arg(ROOT_MOUNT_POINT)
@@ -40,11 +206,80 @@ pub fn prepare_root_filesystem() -> Result<(), std::io::Error> {
     Ok(())
 }
 
-pub fn install_extra_packages() -> Result<(), std::io::Error> {
+fn mmdebstrap(
+    rootfs: &RootfsOptions,
+    target: &str
+) -> Result<(), std::io::Error> {
+    // Cria o sistema de arquivos da raiz (diretório, tarball ou squashfs,
+    // conforme a extensão do destino)
+    let mut command = Command::new("mmdebstrap");
+
+    command
+        .arg(format!("--architectures={}", rootfs.architecture()))
+        .arg("--mode=auto");
+
+    // O proxy vai pela variável de ambiente: o --aptopt seria gravado em
+    // /etc/apt/apt.conf.d/99mmdebstrap e ficaria no novo sistema e no cache
+    if let Some(proxy) = &rootfs.proxy {
+        command.env("http_proxy", proxy);
+    }
+
+    for hook in &rootfs.hooks {
+        command.arg(format!("--{}-hook={}", hook.phase, hook.command));
+    }
+
+    // Instala a seleção de pacotes diretamente, quando a raiz não passará
+    // pelo install_extra_packages
+    if rootfs.include_packages {
+        command.arg(format!("--include={}", rootfs.packages.packages().join(",")));
+
+        for unit in rootfs.packages.units_to_enable() {
+            command.arg(format!("--customize-hook=chroot \"$1\" systemctl enable {}", unit));
+        }
+
+        for unit in &rootfs.packages.disabled_units {
+            command.arg(format!("--customize-hook=chroot \"$1\" systemctl disable {}", unit));
+        }
+
+        for unit in &rootfs.packages.masked_units {
+            command.arg(format!("--customize-hook=chroot \"$1\" systemctl mask {}", unit));
+        }
+    }
+
+    command
+        .arg(&rootfs.suite)
+        .arg(target);
+
+    // O repositório local não é assinado e precisa ser marcado como confiável
+    if rootfs.offline_repo.is_some() {
+        for mirror in rootfs.mirrors() {
+            command.arg(format!("deb [trusted=yes] {} {} main", mirror, rootfs.suite));
+        }
+    } else {
+        command.args(rootfs.mirrors());
+    }
+
+    let output = command.output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Falha ao criar o sistema de arquivos da raiz com o mmdebstrap!"
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn install_extra_packages(
+    apt_options: &[String],
+    selection: &PackageSelection
+) -> Result<(), std::io::Error> {
     // Atualiza o banco de dados do gerenciador de pacotes do novo sistema
     let output = Command::new("chroot")
         .arg(ROOT_MOUNT_POINT)
         .arg("/bin/apt")
+        .args(apt_options)
         .arg("update")
         .output()?;
     
@@ -59,21 +294,9 @@ pub fn install_extra_packages() -> Result<(), std::io::Error> {
     let output = Command::new("chroot")
         .arg(ROOT_MOUNT_POINT)
         .arg("/bin/apt")
+        .args(apt_options)
         .arg("install")
-        .arg("u-boot-tools")
-        .arg("initramfs-tools")
-        .arg("udev")
-        .arg("wget")
-        .arg("curl")
-        .arg("ntpdate")
-        .arg("sudo")
-        .arg("iputils-ping")
-        .arg("network-manager")
-        .arg("openssh-server")
-        .arg("dosfstools")
-        .arg("tar")
-        .arg("zip")
-        .arg("unzip")
+        .args(selection.packages())
         .arg("-y")
         .output()?;
     
@@ -84,55 +307,133 @@ pub fn install_extra_packages() -> Result<(), std::io::Error> {
         ));
     }
 
-    // Habilita o NetworkManager
+    // Remove pacotes do sistema base que o usuário não quer
+    if !selection.removed.is_empty() {
+        let output = Command::new("chroot")
+            .arg(ROOT_MOUNT_POINT)
+            .arg("/bin/apt")
+            .args(apt_options)
+            .arg("purge")
+            .args(&selection.removed)
+            .arg("-y")
+            .output()?;
+
+        if !output.status.success() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "Falha ao remover pacotes do novo sistema!"
+            ));
+        }
+    }
+
+    // Habilita, desabilita e mascara as unidades do systemd
+    for unit in selection.units_to_enable() {
+        run_systemctl("enable", &unit)?;
+    }
+
+    for unit in &selection.disabled_units {
+        run_systemctl("disable", unit)?;
+    }
+
+    for unit in &selection.masked_units {
+        run_systemctl("mask", unit)?;
+    }
+
+    Ok(())
+}
+
+pub fn run_systemctl(
+    action: &str,
+    unit: &str
+) -> Result<(), std::io::Error> {
     let output = Command::new("chroot")
         .arg(ROOT_MOUNT_POINT)
         .arg("/bin/systemctl")
-        .arg("enable")
-        .arg("NetworkManager")
+        .arg(action)
+        .arg(unit)
         .output()?;
-    
+
     if !output.status.success() {
         return Err(std::io::Error::new(
             std::io::ErrorKind::Other,
-            "Falha ao habilitar o NetworkManager!"
+            format!("Falha ao executar systemctl {} {}!", action, unit)
         ));
     }
 
-    // Habilita o SSH
+    Ok(())
+}
+
+pub fn install_kernel_modules(
+    kernel: &KernelFiles
+) -> Result<(), std::io::Error>  {
+    match &kernel.modules_dir {
+        // Copia os módulos já compilados de um pacote ou tarball
+        Some(modules_dir) => {
+            let destination_dir = format!("{}/lib/modules", ROOT_MOUNT_POINT);
+
+            if let Err(_) = fs::create_dir_all(destination_dir.as_str()) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    "Falha ao criar o diretório /lib/modules!"
+                ));
+            }
+
+            let output = Command::new("cp")
+                .arg("-a")
+                .arg(modules_dir)
+                .arg(destination_dir)
+                .output()?;
+
+            if !output.status.success() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    "Falha ao copiar os módulos do kernel!"
+                ));
+            }
+        },
+        // Instala módulos do kernel na raiz do novo sistema
+        None => {
+            let kernel_path = kernel.source_tree.as_deref().unwrap_or_default();
+
+            let output = Command::new("make")
+                .arg("-s")
+                .arg("-C")
+                .arg(kernel_path)
+                .arg(format!("INSTALL_MOD_PATH={}", ROOT_MOUNT_POINT))
+                .arg("modules_install")
+                .output()?;
+
+            if !output.status.success() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    "Falha ao instalar módulos do kernel!"
+                ));
+            }
+        },
+    }
+
+    // Gera as dependências dos módulos com o depmod do novo sistema
     let output = Command::new("chroot")
         .arg(ROOT_MOUNT_POINT)
-        .arg("/bin/systemctl")
-        .arg("enable")
-        .arg("ssh")
+        .arg("/sbin/depmod")
+        .arg("-a")
+        .arg(&kernel.release)
         .output()?;
-    
+
     if !output.status.success() {
         return Err(std::io::Error::new(
             std::io::ErrorKind::Other,
-            "Falha ao habilitar o SSH!"
+            "Falha ao gerar as dependências dos módulos do kernel!"
         ));
     }
 
-    Ok(())
-}
+    // Verifica se os módulos foram instalados no lugar esperado
+    let modules_dep = format!("{}/lib/modules/{}/modules.dep", ROOT_MOUNT_POINT, kernel.release);
 
-pub fn install_kernel_modules(
-    kernel_path: &str
-) -> Result<(), std::io::Error>  {
-    // Instala módulos do kernel
-    let output = Command::new("make")
-        .arg("-s")
-        .arg("-C")
-        .arg(kernel_path)
-        .arg(format!("INSTALL_MOD_DIR={}", ROOT_MOUNT_POINT))
-        .arg("modules_install")
-        .output()?;
-    
-    if !output.status.success() {
+    if !Path::new(modules_dep.as_str()).is_file() {
         return Err(std::io::Error::new(
             std::io::ErrorKind::Other,
-            "Falha ao instalar módulos do kernel!"
+            format!("O arquivo /lib/modules/{}/modules.dep não foi encontrado no novo sistema!", kernel.release)
         ));
     }
 
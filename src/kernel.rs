@@ -0,0 +1,446 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crate::configure::*;
+use crate::uimage::*;
+
+pub enum KernelSource {
+    SourceTree(String),
+    DebPackage(String),
+    Repository(String),
+    Tarball(String),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum KernelArchitecture {
+    Arm,
+    Arm64,
+}
+
+impl KernelArchitecture {
+    pub fn from_config(config_path: &str) -> Result<KernelArchitecture, std::io::Error> {
+        // Detecta a arquitetura do kernel pelo arquivo .config
+        let config = match fs::read_to_string(config_path) {
+            Ok(config) => config,
+            Err(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("Falha ao ler o arquivo {}!", config_path)
+                ));
+            }
+        };
+
+        if config.lines().any(|line| line.trim() == "CONFIG_ARM64=y") {
+            Ok(KernelArchitecture::Arm64)
+        } else if config.lines().any(|line| line.trim() == "CONFIG_ARM=y") {
+            Ok(KernelArchitecture::Arm)
+        } else {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "O kernel informado não é um kernel ARM ou ARM64!"
+            ))
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<KernelArchitecture, std::io::Error> {
+        match name {
+            "arm" | "armhf" => Ok(KernelArchitecture::Arm),
+            "arm64" | "aarch64" => Ok(KernelArchitecture::Arm64),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Arquitetura desconhecida: {}!", name)
+            )),
+        }
+    }
+
+    pub fn image_name(&self) -> &'static str {
+        match self {
+            KernelArchitecture::Arm => "zImage",
+            KernelArchitecture::Arm64 => "Image",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            KernelArchitecture::Arm => "arm",
+            KernelArchitecture::Arm64 => "arm64",
+        }
+    }
+
+    pub fn debian_architecture(&self) -> &'static str {
+        match self {
+            KernelArchitecture::Arm => "armhf",
+            KernelArchitecture::Arm64 => "arm64",
+        }
+    }
+
+    pub fn default_load_address(&self) -> u32 {
+        match self {
+            KernelArchitecture::Arm => 0x600f0000,
+            KernelArchitecture::Arm64 => 0x02080000,
+        }
+    }
+
+    pub fn uimage_architecture(&self) -> ImageArchitecture {
+        match self {
+            KernelArchitecture::Arm => ImageArchitecture::Arm,
+            KernelArchitecture::Arm64 => ImageArchitecture::Arm64,
+        }
+    }
+}
+
+pub struct KernelFiles {
+    pub release: String,
+    pub architecture: KernelArchitecture,
+    pub image: String,
+    pub config: String,
+    pub system_map: Option<String>,
+    pub dtb_dir: String,
+    pub modules_dir: Option<String>,
+    pub source_tree: Option<String>,
+}
+
+impl KernelSource {
+    pub fn detect(kernel: &str) -> KernelSource {
+        // Identifica a origem do kernel pelo argumento informado
+        let tarball_extensions = [".tar", ".tar.gz", ".tgz", ".tar.xz", ".txz", ".tar.bz2"];
+
+        if kernel.ends_with(".deb") {
+            KernelSource::DebPackage(kernel.to_string())
+        } else if tarball_extensions.iter().any(|extension| kernel.ends_with(extension)) {
+            KernelSource::Tarball(kernel.to_string())
+        } else if Path::new(kernel).is_dir() {
+            KernelSource::SourceTree(kernel.to_string())
+        } else {
+            KernelSource::Repository(kernel.to_string())
+        }
+    }
+
+    pub fn dependencies(&self) -> &'static [&'static str] {
+        // Apenas a árvore de código fonte precisa do make
+        match self {
+            KernelSource::SourceTree(_) => &["build-essential"],
+            _ => &[],
+        }
+    }
+
+    pub fn prepare(
+        &self,
+        apt_options: &[String],
+        architecture: Option<&str>,
+        staging_dir: &str
+    ) -> Result<KernelFiles, std::io::Error> {
+        // Localiza os arquivos do kernel, extraindo-os quando necessário
+        match self {
+            KernelSource::SourceTree(kernel_path) => source_tree_files(kernel_path),
+            KernelSource::DebPackage(deb_path) => {
+                create_staging_dir(staging_dir)?;
+                extract_deb_package(deb_path, staging_dir)?;
+                staged_files(staging_dir)
+            },
+            KernelSource::Repository(package) => {
+                create_staging_dir(staging_dir)?;
+                let deb_path = download_kernel_package(package, apt_options, architecture, staging_dir)?;
+                extract_deb_package(&deb_path, staging_dir)?;
+                staged_files(staging_dir)
+            },
+            KernelSource::Tarball(tarball_path) => {
+                create_staging_dir(staging_dir)?;
+                extract_tarball(tarball_path, staging_dir)?;
+                staged_files(staging_dir)
+            },
+        }
+    }
+}
+
+fn source_tree_files(kernel_path: &str) -> Result<KernelFiles, std::io::Error> {
+    // Arquivos de um kernel compilado a partir do código fonte
+    let release = get_kernel_release(kernel_path)?;
+    let config = format!("{}/.config", kernel_path);
+    let architecture = KernelArchitecture::from_config(&config)?;
+    let boot_dir = format!("{}/arch/{}/boot", kernel_path, architecture.name());
+
+    let image = find_existing(&[
+        format!("{}/{}", boot_dir, architecture.image_name()),
+        format!("{}/{}.gz", boot_dir, architecture.image_name()),
+    ]).ok_or_else(|| std::io::Error::new(
+        std::io::ErrorKind::Other,
+        format!("O arquivo {}/{} não foi encontrado!", boot_dir, architecture.image_name())
+    ))?;
+
+    Ok(KernelFiles {
+        release,
+        architecture,
+        image,
+        config,
+        system_map: Some(format!("{}/System.map", kernel_path)),
+        dtb_dir: format!("{}/dts", boot_dir),
+        modules_dir: None,
+        source_tree: Some(kernel_path.to_string()),
+    })
+}
+
+fn create_staging_dir(staging_dir: &str) -> Result<(), std::io::Error> {
+    // Recria o diretório temporário onde o kernel é extraído
+    if Path::new(staging_dir).exists() {
+        if let Err(_) = fs::remove_dir_all(staging_dir) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao limpar o diretório {}!", staging_dir)
+            ));
+        }
+    }
+
+    if let Err(_) = fs::create_dir_all(staging_dir) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao criar o diretório {}!", staging_dir)
+        ));
+    }
+
+    Ok(())
+}
+
+fn extract_deb_package(
+    deb_path: &str,
+    staging_dir: &str
+) -> Result<(), std::io::Error> {
+    // Extrai o conteúdo do pacote linux-image-*.deb
+    let output = Command::new("dpkg-deb")
+        .arg("--extract")
+        .arg(deb_path)
+        .arg(staging_dir)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao extrair o pacote {}!", deb_path)
+        ));
+    }
+
+    Ok(())
+}
+
+fn extract_tarball(
+    tarball_path: &str,
+    staging_dir: &str
+) -> Result<(), std::io::Error> {
+    // Extrai o tarball do kernel
+    let output = Command::new("tar")
+        .arg("--extract")
+        .arg("--file")
+        .arg(tarball_path)
+        .arg("--directory")
+        .arg(staging_dir)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao extrair o tarball {}!", tarball_path)
+        ));
+    }
+
+    Ok(())
+}
+
+fn download_kernel_package(
+    package: &str,
+    apt_options: &[String],
+    architecture: Option<&str>,
+    staging_dir: &str
+) -> Result<String, std::io::Error> {
+    let deb_path = apt_download(package, apt_options, architecture, staging_dir)?;
+
+    // Metapacotes como linux-image-armmp não contêm o kernel, apenas
+    // dependem do pacote versionado
+    let output = Command::new("dpkg-deb")
+        .arg("--field")
+        .arg(&deb_path)
+        .arg("Depends")
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao ler as dependências do pacote {}!", package)
+        ));
+    }
+
+    let depends = String::from_utf8_lossy(&output.stdout).to_string();
+    let versioned_package = depends
+        .split(',')
+        .map(|dependency| dependency.trim().split(' ').next().unwrap_or(""))
+        .find(|dependency| dependency.starts_with("linux-image-"));
+
+    match versioned_package {
+        Some(versioned_package) if !package_has_modules(&deb_path)? => {
+            apt_download(versioned_package, apt_options, architecture, staging_dir)
+        },
+        _ => Ok(deb_path),
+    }
+}
+
+fn apt_download(
+    package: &str,
+    apt_options: &[String],
+    architecture: Option<&str>,
+    staging_dir: &str
+) -> Result<String, std::io::Error> {
+    // Baixa o pacote do repositório configurado no sistema atual. Sem a
+    // arquitetura, o APT usaria a desta máquina, errada em instalações cruzadas
+    let package_spec = match architecture {
+        Some(architecture) => format!("{}:{}", package, architecture),
+        None => package.to_string(),
+    };
+
+    let output = Command::new("apt-get")
+        .current_dir(staging_dir)
+        .args(apt_options)
+        .arg("download")
+        .arg(&package_spec)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!(
+                "Falha ao baixar o pacote {}! Em instalações cruzadas, habilite a arquitetura com dpkg --add-architecture.",
+                package_spec
+            )
+        ));
+    }
+
+    // Localiza o arquivo baixado, nomeado <pacote>_<versão>_<arquitetura>.deb
+    let prefix = format!("{}_", package);
+
+    if let Ok(entries) = fs::read_dir(staging_dir) {
+        for entry in entries.flatten() {
+            let filename = entry.file_name().to_string_lossy().to_string();
+
+            if filename.starts_with(&prefix) && filename.ends_with(".deb") {
+                return Ok(entry.path().to_string_lossy().to_string());
+            }
+        }
+    }
+
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        format!("O pacote {} não foi encontrado após o download!", package)
+    ))
+}
+
+fn package_has_modules(deb_path: &str) -> Result<bool, std::io::Error> {
+    let output = Command::new("dpkg-deb")
+        .arg("--contents")
+        .arg(deb_path)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao listar o conteúdo do pacote {}!", deb_path)
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).contains("/lib/modules/"))
+}
+
+fn staged_files(staging_dir: &str) -> Result<KernelFiles, std::io::Error> {
+    // Localiza os arquivos do kernel extraído, aceitando tanto o layout dos
+    // pacotes Debian quanto o de tarballs com zImage/Image, dtbs/ e lib/modules/
+    let modules_root = find_existing(&[
+        format!("{}/lib/modules", staging_dir),
+        format!("{}/usr/lib/modules", staging_dir),
+    ]).ok_or_else(|| std::io::Error::new(
+        std::io::ErrorKind::Other,
+        "O kernel informado não contém o diretório lib/modules!"
+    ))?;
+
+    let release = single_subdirectory(&modules_root)?;
+
+    let image = find_existing(&[
+        format!("{}/boot/vmlinuz-{}", staging_dir, release),
+        format!("{}/boot/zImage-{}", staging_dir, release),
+        format!("{}/boot/Image-{}", staging_dir, release),
+        format!("{}/boot/zImage", staging_dir),
+        format!("{}/boot/Image", staging_dir),
+        format!("{}/boot/Image.gz", staging_dir),
+        format!("{}/zImage", staging_dir),
+        format!("{}/Image", staging_dir),
+        format!("{}/Image.gz", staging_dir),
+    ]).ok_or_else(|| std::io::Error::new(
+        std::io::ErrorKind::Other,
+        "A imagem do kernel não foi encontrada!"
+    ))?;
+
+    let config = find_existing(&[
+        format!("{}/boot/config-{}", staging_dir, release),
+        format!("{}/boot/config", staging_dir),
+        format!("{}/config", staging_dir),
+        format!("{}/.config", staging_dir),
+    ]).ok_or_else(|| std::io::Error::new(
+        std::io::ErrorKind::Other,
+        "O arquivo de configuração do kernel não foi encontrado!"
+    ))?;
+
+    let architecture = KernelArchitecture::from_config(&config)?;
+
+    let system_map = find_existing(&[
+        format!("{}/boot/System.map-{}", staging_dir, release),
+        format!("{}/boot/System.map", staging_dir),
+        format!("{}/System.map", staging_dir),
+    ]);
+
+    let dtb_dir = find_existing(&[
+        format!("{}/usr/lib/linux-image-{}", staging_dir, release),
+        format!("{}/boot/dtbs/{}", staging_dir, release),
+        format!("{}/boot/dtbs", staging_dir),
+        format!("{}/dtbs", staging_dir),
+    ]).ok_or_else(|| std::io::Error::new(
+        std::io::ErrorKind::Other,
+        "O diretório com os arquivos DTB não foi encontrado!"
+    ))?;
+
+    Ok(KernelFiles {
+        modules_dir: Some(format!("{}/{}", modules_root, release)),
+        release,
+        architecture,
+        image,
+        config,
+        system_map,
+        dtb_dir,
+        source_tree: None,
+    })
+}
+
+fn find_existing(candidates: &[String]) -> Option<String> {
+    candidates
+        .iter()
+        .find(|candidate| Path::new(candidate.as_str()).exists())
+        .cloned()
+}
+
+fn single_subdirectory(directory: &str) -> Result<String, std::io::Error> {
+    // Obtém a versão do kernel pelo nome do único diretório em lib/modules
+    let mut releases: Vec<String> = Vec::new();
+
+    if let Ok(entries) = fs::read_dir(directory) {
+        for entry in entries.flatten() {
+            if entry.path().is_dir() {
+                releases.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+
+    if releases.len() != 1 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Era esperada uma única versão do kernel em {}!", directory)
+        ));
+    }
+
+    Ok(releases.remove(0))
+}
\ No newline at end of file
@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+use std::fs;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+pub struct ConfigIssue {
+    pub option: String,
+    pub severity: Severity,
+    pub reason: String,
+}
+
+pub struct KernelRequirements {
+    pub dtb_file: String,
+    pub board_options: Vec<String>,
+}
+
+// Opções exigidas pelo systemd e pelo Debian
+const SYSTEMD_REQUIRED: &[&str] = &[
+    "CGROUPS",
+    "DEVTMPFS",
+    "INOTIFY_USER",
+    "SIGNALFD",
+    "TIMERFD",
+    "EPOLL",
+    "UNIX",
+    "SYSFS",
+    "PROC_FS",
+    "FHANDLE",
+    "BLK_DEV_INITRD",
+];
+
+// Opções recomendadas, sem as quais o sistema inicia com limitações
+const SYSTEMD_RECOMMENDED: &[&str] = &[
+    "DEVTMPFS_MOUNT",
+    "TMPFS_POSIX_ACL",
+    "SECCOMP",
+    "AUTOFS_FS",
+    "CGROUP_BPF",
+];
+
+pub fn read_kernel_config(
+    config_path: &str
+) -> Result<HashMap<String, String>, std::io::Error> {
+    // Lê as opções definidas no .config, sem o prefixo CONFIG_
+    let contents = match fs::read_to_string(config_path) {
+        Ok(contents) => contents,
+        Err(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao ler o arquivo {}!", config_path)
+            ));
+        }
+    };
+
+    Ok(parse_kernel_config(&contents))
+}
+
+pub fn parse_kernel_config(
+    contents: &str
+) -> HashMap<String, String> {
+    let mut options = HashMap::new();
+
+    for line in contents.lines() {
+        if let Some((option, value)) = line.trim().split_once('=') {
+            if let Some(option) = option.strip_prefix("CONFIG_") {
+                options.insert(option.to_string(), value.to_string());
+            }
+        }
+    }
+
+    options
+}
+
+pub fn check_kernel_config(
+    config_path: &str,
+    requirements: &KernelRequirements
+) -> Result<Vec<ConfigIssue>, std::io::Error> {
+    let config = read_kernel_config(config_path)?;
+
+    Ok(kernel_config_issues(&config, requirements))
+}
+
+pub fn kernel_config_issues(
+    config: &HashMap<String, String>,
+    requirements: &KernelRequirements
+) -> Vec<ConfigIssue> {
+    let mut issues: Vec<ConfigIssue> = Vec::new();
+
+    for option in SYSTEMD_REQUIRED {
+        require(config, &mut issues, option, Severity::Error, "exigida pelo systemd");
+    }
+
+    for option in SYSTEMD_RECOMMENDED {
+        require(config, &mut issues, option, Severity::Warning, "recomendada pelo systemd");
+    }
+
+    // Sistema de arquivos da raiz, sempre ext4
+    require(config, &mut issues, "EXT4_FS", Severity::Error, "necessária para montar a raiz");
+
+    // Controladores do eMMC
+    require(config, &mut issues, "MMC", Severity::Error, "necessária para acessar o eMMC");
+    require(config, &mut issues, "MMC_BLOCK", Severity::Error, "necessária para acessar o eMMC");
+
+    if let Some(driver) = soc_mmc_driver(&requirements.dtb_file) {
+        require(config, &mut issues, driver, Severity::Error, "controlador do eMMC do SoC");
+    }
+
+    // Periféricos do aparelho (Wi-Fi, controle remoto, LEDs)
+    for option in &requirements.board_options {
+        require(config, &mut issues, option, Severity::Warning, "necessária para os periféricos do aparelho");
+    }
+
+    issues
+}
+
+fn require(
+    config: &HashMap<String, String>,
+    issues: &mut Vec<ConfigIssue>,
+    option: &str,
+    severity: Severity,
+    reason: &str
+) {
+    // Opções compiladas como módulo (=m) são aceitas, pois o initramfs as carrega
+    match config.get(option).map(|value| value.as_str()) {
+        Some("y") | Some("m") => {},
+        _ => issues.push(ConfigIssue {
+            option: format!("CONFIG_{}", option),
+            severity,
+            reason: reason.to_string(),
+        }),
+    }
+}
+
+fn soc_mmc_driver(dtb_file: &str) -> Option<&'static str> {
+    // Deduz o controlador do eMMC pela família do DTB escolhido
+    let filename = dtb_file.rsplit('/').next().unwrap_or(dtb_file);
+
+    // Nos Rockchip, o eMMC do rk3399 usa o sdhci-of-arasan e o dos rk35xx o
+    // sdhci-of-dwcmshc; os mais antigos usam o dw_mmc
+    if filename.starts_with("rk3399") {
+        Some("MMC_SDHCI_OF_ARASAN")
+    } else if filename.starts_with("rk35") {
+        Some("MMC_SDHCI_OF_DWCMSHC")
+    } else if filename.starts_with("rk") {
+        Some("MMC_DW_ROCKCHIP")
+    } else if filename.starts_with("meson-g") {
+        Some("MMC_MESON_GX")
+    } else if filename.starts_with("meson8") {
+        Some("MMC_MESON_MX_SDHC")
+    } else if filename.starts_with("sun") {
+        Some("MMC_SUNXI")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Trecho de um .config com todas as opções exigidas
+    const COMPLETE_CONFIG: &str = "\
+#
+# Automatically generated file; DO NOT EDIT.
+#
+CONFIG_CGROUPS=y
+CONFIG_DEVTMPFS=y
+CONFIG_DEVTMPFS_MOUNT=y
+CONFIG_INOTIFY_USER=y
+CONFIG_SIGNALFD=y
+CONFIG_TIMERFD=y
+CONFIG_EPOLL=y
+CONFIG_UNIX=y
+CONFIG_SYSFS=y
+CONFIG_PROC_FS=y
+CONFIG_FHANDLE=y
+CONFIG_BLK_DEV_INITRD=y
+CONFIG_TMPFS_POSIX_ACL=y
+CONFIG_SECCOMP=y
+CONFIG_AUTOFS_FS=m
+CONFIG_CGROUP_BPF=y
+CONFIG_EXT4_FS=y
+CONFIG_MMC=y
+CONFIG_MMC_BLOCK=y
+CONFIG_MMC_DW_ROCKCHIP=y
+CONFIG_LOCALVERSION=\"-rk322x\"
+";
+
+    fn requirements(
+        dtb_file: &str
+    ) -> KernelRequirements {
+        KernelRequirements {
+            dtb_file: dtb_file.to_string(),
+            board_options: Vec::new(),
+        }
+    }
+
+    fn issue_options(
+        issues: &[ConfigIssue]
+    ) -> Vec<&str> {
+        issues.iter().map(|issue| issue.option.as_str()).collect()
+    }
+
+    #[test]
+    fn parses_options_and_ignores_comments() {
+        let config = parse_kernel_config("# CONFIG_SWAP is not set\nCONFIG_EXT4_FS=y\n  CONFIG_MMC=m  \nCONFIG_LOCALVERSION=\"-rk322x\"\nNOT_CONFIG=y\n");
+
+        assert_eq!(config.len(), 3);
+        assert_eq!(config.get("EXT4_FS").map(String::as_str), Some("y"));
+        assert_eq!(config.get("MMC").map(String::as_str), Some("m"));
+        assert_eq!(config.get("LOCALVERSION").map(String::as_str), Some("\"-rk322x\""));
+        assert!(!config.contains_key("SWAP"));
+    }
+
+    #[test]
+    fn accepts_complete_config() {
+        let config = parse_kernel_config(COMPLETE_CONFIG);
+
+        assert!(kernel_config_issues(&config, &requirements("rk322x-box.dtb")).is_empty());
+    }
+
+    #[test]
+    fn reports_missing_options_by_severity() {
+        let contents = COMPLETE_CONFIG
+            .replace("CONFIG_FHANDLE=y", "# CONFIG_FHANDLE is not set")
+            .replace("CONFIG_SECCOMP=y\n", "");
+        let issues = kernel_config_issues(&parse_kernel_config(&contents), &requirements("rk322x-box.dtb"));
+
+        assert_eq!(issue_options(&issues), vec!["CONFIG_FHANDLE", "CONFIG_SECCOMP"]);
+        assert!(issues[0].severity == Severity::Error);
+        assert!(issues[1].severity == Severity::Warning);
+    }
+
+    #[test]
+    fn requires_mmc_driver_of_each_soc() {
+        let config = parse_kernel_config(COMPLETE_CONFIG);
+
+        let cases = [
+            ("rk322x-box.dtb", vec![]),
+            ("rockchip/rk3328-a1.dtb", vec![]),
+            ("rockchip/rk3399-rockpro64.dtb", vec!["CONFIG_MMC_SDHCI_OF_ARASAN"]),
+            ("rockchip/rk3568-evb1-v10.dtb", vec!["CONFIG_MMC_SDHCI_OF_DWCMSHC"]),
+            ("amlogic/meson-gxl-s905x-p212.dtb", vec!["CONFIG_MMC_MESON_GX"]),
+            ("allwinner/sun50i-h6-tanix-tx6.dtb", vec!["CONFIG_MMC_SUNXI"]),
+            ("desconhecido.dtb", vec![]),
+        ];
+
+        for (dtb_file, expected) in cases {
+            assert_eq!(issue_options(&kernel_config_issues(&config, &requirements(dtb_file))), expected, "{}", dtb_file);
+        }
+    }
+
+    #[test]
+    fn reports_board_options_as_warnings() {
+        let config = parse_kernel_config(COMPLETE_CONFIG);
+        let requirements = KernelRequirements {
+            dtb_file: "rk322x-box.dtb".to_string(),
+            board_options: vec!["MMC_DW_ROCKCHIP".to_string(), "SSV6200_COMMON".to_string()],
+        };
+        let issues = kernel_config_issues(&config, &requirements);
+
+        assert_eq!(issue_options(&issues), vec!["CONFIG_SSV6200_COMMON"]);
+        assert!(issues[0].severity == Severity::Warning);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,227 @@
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+use crate::configure_boot::*;
+use crate::constants::*;
+use crate::overlays::*;
+
+pub fn install_kernel_hooks(
+    boot: &BootConfig
+) -> Result<(), std::io::Error> {
+    write_hook("/etc/kernel/postinst.d", &render_postinst_hook(boot))?;
+    write_hook("/etc/initramfs/post-update.d", &render_post_update_hook(boot))?;
+
+    Ok(())
+}
+
+pub fn render_postinst_hook(
+    boot: &BootConfig
+) -> String {
+    let architecture = boot.architecture.name();
+    let image_name = boot.architecture.image_name();
+    let load_address = boot.load_address;
+
+    // Hook executado após a instalação de um kernel por pacote: copia a
+    // imagem e os DTBs para os nomes usados pelo instalador, gera o uImage e
+    // atualiza os links simbólicos
+    let mut postinst = String::new();
+
+    postinst += "#!/bin/sh\n";
+    postinst += "# Gerado pelo instalador. Mantém os arquivos de boot do U-Boot atualizados.\n";
+    postinst += "set -e\n\n";
+    postinst += "version=\"$1\"\n";
+    postinst += "[ -n \"$version\" ] || exit 0\n";
+    postinst += "[ -f \"/boot/vmlinuz-$version\" ] || exit 0\n\n";
+    postinst += format!("image=\"/boot/{}-$version\"\n\n", image_name).as_str();
+    postinst += "if gzip -t \"/boot/vmlinuz-$version\" 2>/dev/null; then\n";
+    postinst += "    gzip -dc \"/boot/vmlinuz-$version\" > \"$image\"\n";
+    postinst += "else\n";
+    postinst += "    cp \"/boot/vmlinuz-$version\" \"$image\"\n";
+    postinst += "fi\n\n";
+    postinst += "if [ -d \"/usr/lib/linux-image-$version\" ]; then\n";
+    postinst += "    mkdir -p \"/boot/dtb-$version\"\n";
+    postinst += "    cp -r \"/usr/lib/linux-image-$version/.\" \"/boot/dtb-$version/\"\n";
+    postinst += "fi\n\n";
+    postinst += "dtb_file=$(readlink /boot/device_tree_binary.dtb | sed 's|^/boot/dtb-[^/]*/||')\n\n";
+
+    // Os overlays escolhidos na instalação acompanham o novo kernel
+    if !boot.overlays.is_empty() {
+        let overlays: Vec<String> = boot.overlays.iter().map(|overlay| overlay_file_name(overlay)).collect();
+
+        postinst += "mkdir -p \"/boot/dtb-$version/overlays\"\n";
+        postinst += format!("for overlay in {}; do\n", overlays.join(" ")).as_str();
+        postinst += "    cp \"/boot/dtb/overlays/$overlay\" \"/boot/dtb-$version/overlays/$overlay\"\n";
+        postinst += "done\n\n";
+
+        if boot.merges_overlays() {
+            let overlay_paths: Vec<String> = overlays
+                .iter()
+                .map(|overlay| format!("\"/boot/dtb-$version/overlays/{}\"", overlay))
+                .collect();
+
+            // Só aplica sobre um DTB recém-copiado do pacote do kernel
+            postinst += "if [ -d \"/usr/lib/linux-image-$version\" ]; then\n";
+            postinst += format!(
+                "    fdtoverlay --input \"/boot/dtb-$version/$dtb_file\" --output \"/boot/dtb-$version/$dtb_file\" {}\n",
+                overlay_paths.join(" ")
+            ).as_str();
+            postinst += "fi\n\n";
+        }
+    }
+
+    postinst += format!(
+        "mkimage -A {0} -O linux -T kernel -C none -a {1:#010x} -e {1:#010x} -n \"$version\" -d \"$image\" \"/boot/uImage-$version\" > /dev/null\n\n",
+        architecture,
+        load_address
+    ).as_str();
+    postinst += &symbolic_link_functions();
+    postinst += format!("rotate {} \"/boot/{}-$version\"\n", image_name, image_name).as_str();
+    // Sem o DTB no novo kernel, os links atuais são mantidos para não
+    // apontar para um arquivo inexistente
+    postinst += "if [ -n \"$dtb_file\" ] && [ -f \"/boot/dtb-$version/$dtb_file\" ]; then\n";
+    postinst += "    rotate dtb \"/boot/dtb-$version\"\n";
+    postinst += "    rotate device_tree_binary.dtb \"/boot/dtb-$version/$dtb_file\"\n";
+    postinst += "else\n";
+    postinst += "    echo \"AVISO: O DTB $dtb_file não foi encontrado em /boot/dtb-$version; os links atuais foram mantidos.\" >&2\n";
+    postinst += "fi\n";
+    postinst += "rotate uImage \"/boot/uImage-$version\"\n";
+    postinst += "if [ -f \"/boot/initrd.img-$version\" ]; then\n";
+    postinst += "    rotate initrd.img \"/boot/initrd.img-$version\"\n";
+    postinst += "fi\n";
+    postinst += "if [ -f \"/boot/uInitrd-$version\" ]; then\n";
+    postinst += "    rotate uInitrd \"/boot/uInitrd-$version\"\n";
+    postinst += "fi\n\n";
+
+    // Em aparelhos com FIT, a imagem .itb é gerada a partir da descrição do
+    // kernel anterior, trocando apenas a versão
+    postinst += "previous=$(readlink /boot/image.itb || true)\n";
+    postinst += "if [ -n \"$previous\" ]; then\n";
+    postinst += "    previous=${previous#/boot/image-}\n";
+    postinst += "    previous=${previous%.itb}\n";
+    postinst += "    if [ \"$previous\" != \"$version\" ]; then\n";
+    postinst += "        sed \"s|$previous|$version|g\" \"/boot/image-$previous.its\" > \"/boot/image-$version.its\"\n";
+    postinst += "    fi\n";
+    postinst += "    (cd /boot && mkimage -f \"image-$version.its\" \"image-$version.itb\" > /dev/null)\n";
+    postinst += "    rotate image.itb \"/boot/image-$version.itb\"\n";
+    postinst += "fi\n";
+
+    postinst
+}
+
+pub fn render_post_update_hook(
+    boot: &BootConfig
+) -> String {
+    let architecture = boot.architecture.name();
+
+    // Hook executado após cada atualização do initramfs: gera o uInitrd
+    let mut post_update = String::new();
+
+    post_update += "#!/bin/sh\n";
+    post_update += "# Gerado pelo instalador. Mantém o uInitrd atualizado.\n";
+    post_update += "set -e\n\n";
+    post_update += "version=\"$1\"\n";
+    post_update += "initrd=\"${2:-/boot/initrd.img-$version}\"\n";
+    post_update += "[ -n \"$version\" ] && [ -f \"$initrd\" ] || exit 0\n\n";
+    post_update += format!(
+        "mkimage -A {} -O linux -T ramdisk -a 0x0 -e 0x0 -n \"initrd.img-$version\" -d \"$initrd\" \"/boot/uInitrd-$version\" > /dev/null\n",
+        architecture
+    ).as_str();
+
+    // Com FIT, o initrd fica dentro da imagem .itb, que precisa ser gerada
+    // novamente para não iniciar com o initrd antigo
+    post_update += "\nif [ -f \"/boot/image-$version.its\" ]; then\n";
+    post_update += "    (cd /boot && mkimage -f \"image-$version.its\" \"image-$version.itb\" > /dev/null)\n";
+    post_update += "fi\n";
+
+    post_update
+}
+
+fn symbolic_link_functions() -> String {
+    // Troca os links de forma atômica, guardando o destino anterior em <link>.old
+    let mut functions = String::new();
+
+    functions += "replace() {\n";
+    functions += "    ln -sfn \"$2\" \"$1.tmp\"\n";
+    functions += "    mv -Tf \"$1.tmp\" \"$1\"\n";
+    functions += "}\n\n";
+    functions += "rotate() {\n";
+    functions += "    current=$(readlink \"/boot/$1\" || true)\n";
+    functions += "    if [ -n \"$current\" ] && [ \"$current\" != \"$2\" ]; then\n";
+    functions += "        replace \"/boot/$1.old\" \"$current\"\n";
+    functions += "    fi\n";
+    functions += "    replace \"/boot/$1\" \"$2\"\n";
+    functions += "}\n\n";
+
+    functions
+}
+
+fn write_hook(
+    directory: &str,
+    contents: &str
+) -> Result<(), std::io::Error> {
+    // Cria o diretório do hook no novo sistema
+    let hook_dir = format!("{}{}", ROOT_MOUNT_POINT, directory);
+
+    if let Err(_) = fs::create_dir_all(hook_dir.as_str()) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao criar o diretório {}!", directory)
+        ));
+    }
+
+    // Grava o hook como executável
+    let filepath = format!("{}/{}", hook_dir, KERNEL_HOOK_NAME);
+
+    if let Err(_) = fs::write(filepath.as_str(), contents) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao criar o arquivo {}/{}!", directory, KERNEL_HOOK_NAME)
+        ));
+    }
+
+    if let Err(_) = fs::set_permissions(filepath.as_str(), fs::Permissions::from_mode(0o755)) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao tornar o arquivo {}/{} executável!", directory, KERNEL_HOOK_NAME)
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::golden::*;
+    use crate::soc_profiles::*;
+
+    fn boot_config(
+        soc: &str,
+        boot_format: BootFormat,
+        overlays: &[&str]
+    ) -> BootConfig {
+        let soc = find_soc_profile(soc).unwrap();
+        let options = BootOptions {
+            overlays: overlays.iter().map(|overlay| overlay.to_string()).collect(),
+            ..BootOptions::default()
+        };
+
+        create_boot_config(soc.architecture, Some(soc), soc.default_dtb, boot_format, &options).unwrap()
+    }
+
+    #[test]
+    fn renders_legacy_hooks() {
+        let boot = boot_config("rk322x", BootFormat::Legacy, &[]);
+
+        assert_golden("postinst-legacy", &render_postinst_hook(&boot));
+        assert_golden("post-update-legacy", &render_post_update_hook(&boot));
+    }
+
+    #[test]
+    fn renders_fit_hooks_with_merged_overlays() {
+        let boot = boot_config("rk3328", BootFormat::Fit, &["rk3328-uart1"]);
+
+        assert_golden("postinst-fit-overlays", &render_postinst_hook(&boot));
+        assert_golden("post-update-fit", &render_post_update_hook(&boot));
+    }
+}
\ No newline at end of file
@@ -1,18 +1,69 @@
+// O projeto constrói os erros com std::io::Error::new(ErrorKind::Other, ...)
+// e testa falhas com `if let Err(_) = ...` em todos os módulos; as lints
+// abaixo, introduzidas em versões recentes do clippy, reprovariam esse padrão
+#![allow(clippy::io_other_error)]
+#![allow(clippy::redundant_pattern_matching)]
+
+mod apt_config;
+mod boot_script;
+mod bootloader;
+mod board_detection;
+mod boards;
 mod configure;
 mod configure_boot;
 mod configure_storage;
 mod constants;
 mod dependencies;
+mod fdt;
+mod fit_image;
+#[cfg(test)]
+mod golden;
 mod install;
+mod kernel;
+mod kernel_config;
+mod kernel_hooks;
+mod offline;
+mod options;
+mod overlays;
+mod package_sets;
+mod profile;
+mod rootfs_cache;
+mod soc_profiles;
+mod test_boot;
+mod uimage;
+mod update_kernel;
+mod verify;
 
 use std::env;
+use std::path::Path;
 use std::process::exit;
 
+use apt_config::*;
+use boot_script::*;
+use bootloader::*;
+use board_detection::*;
+use boards::*;
 use configure::*;
 use configure_boot::*;
 use configure_storage::*;
+use constants::*;
 use dependencies::*;
+use fdt::*;
+use fit_image::*;
 use install::*;
+use kernel::*;
+use kernel_config::*;
+use kernel_hooks::*;
+use offline::*;
+use options::*;
+use overlays::*;
+use profile::*;
+use rootfs_cache::*;
+use soc_profiles::*;
+use test_boot::*;
+use uimage::*;
+use update_kernel::*;
+use verify::*;
 
 fn main() {
     // Verifica se o usuário atual é o usuário root
@@ -29,19 +80,64 @@ fn main() {
     // Obtém argumentos do terminal
     let args: Vec<String> = env::args().collect();
 
-    // Verifica se o número de argumentos está correto
-    if args.len() != 4 {
-        eprintln!("\nUso: {} <emmc> <kernel> <dtb>\n\nOnde:\n", args[0]);
-        eprintln!("  <emmc>    Caminho para dispositivo eMMC.\n            Exemplo: /dev/mmcblk0\n");
-        eprintln!("  <kernel>  Caminho para o diretório do kernel Linux compilado.\n            Exemplo: /mnt/pendrive/linux-6.1.57\n");
-        eprintln!("  <dtb>     Nome do arquivo em <kernel>/arch/arm/boot/dts que deve ser usado pelo kernel.\n            Exemplo: rk322x-box.dtb\n");
-        exit(1);
+    // Executa subcomandos
+    if args.len() > 1 {
+        match args[1].as_str() {
+            "build-rootfs" => {
+                build_rootfs(&args);
+                return;
+            },
+            "prepare-offline-repo" => {
+                prepare_offline_repo(&args);
+                return;
+            },
+            "update-kernel" => {
+                update_kernel(&args);
+                return;
+            },
+            "inspect-uimage" => {
+                inspect_uimage(&args);
+                return;
+            },
+            "list-boards" => {
+                list_boards(&args);
+                return;
+            },
+            "inspect-dtb" => {
+                inspect_dtb(&args);
+                return;
+            },
+            "verify" => {
+                verify(&args);
+                return;
+            },
+            "test-boot" => {
+                test_boot(&args);
+                return;
+            },
+            _ => {}
+        }
     }
 
-    // Obtém argumentos
-    let storage_device_path = &args[1];
-    let kernel_path = &args[2];
-    let dtb_file = &args[3];
+    // Interpreta os argumentos e opções
+    let mut options = match parse_install_options(&args) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("ERRO: {}", error);
+            print_usage(&args[0]);
+            exit(1);
+        }
+    };
+
+    let storage_device_path = options.storage_device_path.as_str();
+
+    // Kernels adicionais são instalados antes, de modo que o principal
+    // fique como padrão e o anterior como alternativa no menu de boot
+    let kernel_sources: Vec<KernelSource> = options.extra_kernels
+        .iter()
+        .chain(std::iter::once(&options.kernel))
+        .map(|kernel| KernelSource::detect(kernel))
+        .collect();
 
     // Verifica se o dispositivo informado é um eMMC válido
     if !storage_device_path.contains("mmcblk") {
@@ -49,20 +145,6 @@ fn main() {
         exit(1);
     }
 
-    // Obtém versão do kernel
-    let release: String;
-    match get_kernel_release(&kernel_path) {
-        Ok(kernel_release) => {
-            println!("A versão do kernel foi obtida com sucesso.");
-            release = kernel_release;
-        },
-        Err(error) => {
-            eprintln!("ERRO: {}", error);
-            exit(1);
-        }
-    }
-    let kernel_release = release.as_str();
-
     // Obtém nome da máquina e senha do usuário root
     let hostname = get_hostname();
     let root_password = get_root_password();
@@ -70,10 +152,69 @@ fn main() {
     // Gera caminho para a partição raiz
     let root_partition_path = format!("{}p1", storage_device_path);
 
+    // Configuração dos arquivos do novo sistema
+    let system = SystemConfig {
+        hostname,
+        suite: options.rootfs.suite.clone(),
+        mirror: options.rootfs.target_mirror(),
+        security_mirror: options.security_mirror.clone(),
+        root_device: root_partition_path.clone(),
+    };
+
+
+    // Arquitetura conhecida antes de obter o kernel: a informada com
+    // --architecture ou, na falta dela, a do SoC
+    let target_architecture = match &options.rootfs.architecture {
+        Some(architecture) => Some(architecture.clone()),
+        None => options.soc.map(|soc| soc.architecture.debian_architecture().to_string()),
+    };
+
 
     // INSTALA DEPENDÊNCIAS DO INSTALADOR
 
-    match install_dependencies() {
+    // No modo offline o APT usa apenas o repositório local
+    if options.rootfs.offline_repo.is_some() {
+        let architecture = match &target_architecture {
+            Some(architecture) => architecture,
+            None => {
+                eprintln!("ERRO: O modo offline exige --architecture, --soc ou --board!");
+                exit(1);
+            }
+        };
+
+        match check_offline_architecture(architecture) {
+            Ok(()) => println!("A arquitetura desta máquina é compatível com o repositório local."),
+            Err(error) => {
+                eprintln!("ERRO: {}", error);
+                exit(1);
+            }
+        }
+    }
+
+    let mut host_apt_options = match &options.rootfs.offline_repo {
+        Some(repo_dir) => match write_offline_source_list(
+            OFFLINE_SOURCE_LIST,
+            repo_dir,
+            &options.rootfs.suite
+        ) {
+            Ok(()) => offline_apt_options(OFFLINE_SOURCE_LIST),
+            Err(error) => {
+                eprintln!("ERRO: {}", error);
+                exit(1);
+            }
+        },
+        None => Vec::new(),
+    };
+    host_apt_options.extend(options.rootfs.proxy_apt_options());
+
+    let kernel_dependencies: Vec<&str> = kernel_sources
+        .iter()
+        .flat_map(|kernel_source| kernel_source.dependencies().iter().copied())
+        .chain(options.boot_format.dependencies().iter().copied())
+        .chain(overlay_dependencies(&options.boot.overlays, options.boot.merge_overlays).iter().copied())
+        .collect();
+
+    match install_dependencies(&host_apt_options, &kernel_dependencies) {
         Ok(()) => println!("As dependências do instalador foram instaladas com sucesso."),
         Err(error) => {
             eprintln!("ERRO: {}", error);
@@ -82,9 +223,84 @@ fn main() {
     }
 
 
+    // PREPARA O KERNEL
+
+    // Obtém versões e arquivos dos kernels antes de formatar o armazenamento
+    let mut kernels: Vec<KernelFiles> = Vec::new();
+
+    for (index, kernel_source) in kernel_sources.iter().enumerate() {
+        let staging_dir = format!("{}/{}", KERNEL_STAGING_DIR, index);
+
+        // O pacote do repositório precisa da arquitetura do novo sistema
+        if let KernelSource::Repository(_) = kernel_source {
+            if target_architecture.is_none() {
+                eprintln!("ERRO: Informe --architecture, --soc ou --board para baixar o kernel do repositório!");
+                exit(1);
+            }
+        }
+
+        let kernel = match kernel_source.prepare(&host_apt_options, target_architecture.as_deref(), &staging_dir) {
+            Ok(kernel) => {
+                println!("A versão do kernel foi obtida com sucesso ({}).", kernel.release);
+                kernel
+            },
+            Err(error) => {
+                eprintln!("ERRO: {}", error);
+                exit(1);
+            }
+        };
+
+        kernels.push(kernel);
+    }
+
+    // O último kernel da lista é o padrão
+    let kernel = &kernels[kernels.len() - 1];
+    let dtb_file = select_dtb_file(&options.dtb_file, kernel);
+    let dtb_file = dtb_file.as_str();
+
+    for kernel in &kernels {
+        check_dtb(kernel, dtb_file);
+        check_overlays(kernel, &options.boot.overlays);
+        check_kernel(kernel, dtb_file, options.board.as_ref(), options.ignore_kernel_config);
+    }
+
+    // Parâmetros de boot do SoC escolhido
+    let boot = match create_boot_config(
+        kernel.architecture,
+        options.soc,
+        dtb_file,
+        options.boot_format,
+        &options.boot
+    ) {
+        Ok(boot) => boot,
+        Err(error) => {
+            eprintln!("ERRO: {}", error);
+            exit(1);
+        }
+    };
+
+    // Verifica o carregador antes de apagar o eMMC
+    let bootloader_images = if options.bootloader.is_empty() {
+        Vec::new()
+    } else {
+        check_bootloader(&options.bootloader, options.soc)
+    };
+
+    // A raiz segue a arquitetura do kernel, salvo indicação em contrário
+    if options.rootfs.architecture.is_none() {
+        options.rootfs.architecture = Some(kernel.architecture.debian_architecture().to_string());
+    }
+
+
     // CONFIGURA ARMAZENAMENTO
 
-    match configure_storage(storage_device_path, &root_partition_path) {
+    let first_sector = if bootloader_images.is_empty() {
+        None
+    } else {
+        Some(ROCKCHIP_RESERVED_SECTORS)
+    };
+
+    match configure_storage(storage_device_path, &root_partition_path, first_sector) {
         Ok(()) => println!("O dispositivo de armazenamento foi formatado com sucesso."),
         Err(error) => {
             eprintln!("ERRO: {}", error);
@@ -92,37 +308,223 @@ fn main() {
         }
     }
 
+    if !bootloader_images.is_empty() {
+        match check_partition_layout(&root_partition_path, &bootloader_images) {
+            Ok(()) => println!("A área do carregador está livre de partições."),
+            Err(error) => {
+                eprintln!("ERRO: {}", error);
+                exit(1);
+            }
+        }
+
+        match write_bootloader(storage_device_path, &bootloader_images) {
+            Ok(()) => println!("O carregador foi gravado e conferido com sucesso."),
+            Err(error) => {
+                eprintln!("ERRO: {}", error);
+                exit(1);
+            }
+        }
+    }
+
 
     // INSTALA O SISTEMA
 
-    match create_root_filesystem() {
-        Ok(()) => println!("O sistema de arquivos da raiz foi criado com sucesso."),
+    let cache_key = match rootfs_cache_key(&options.rootfs, &options.apt_config) {
+        Ok(cache_key) => cache_key,
         Err(error) => {
             eprintln!("ERRO: {}", error);
             exit(1);
         }
+    };
+    let cached_rootfs = options.rootfs_cache_dir
+        .as_ref()
+        .and_then(|cache_dir| find_cached_rootfs(cache_dir, &cache_key));
+
+    if let Some(tarball) = cached_rootfs {
+        match extract_cached_rootfs(&tarball) {
+            Ok(()) => println!("O sistema de arquivos da raiz foi extraído do cache com sucesso."),
+            Err(error) => {
+                eprintln!("ERRO: {}", error);
+                exit(1);
+            }
+        }
+
+        match reset_system_identity() {
+            Ok(()) => println!("O machine-id e as chaves SSH do novo sistema foram regenerados com sucesso."),
+            Err(error) => {
+                eprintln!("ERRO: {}", error);
+                exit(1);
+            }
+        }
+    } else {
+        match create_root_filesystem(&options.rootfs, ROOT_MOUNT_POINT) {
+            Ok(()) => println!("O sistema de arquivos da raiz foi criado com sucesso ({}).", options.rootfs.backend.name()),
+            Err(error) => {
+                eprintln!("ERRO: {}", error);
+                exit(1);
+            }
+        }
+
+        match prepare_root_filesystem(&options.rootfs) {
+            Ok(()) => println!("O sistema de arquivos da raiz foi preparado com sucesso."),
+            Err(error) => {
+                eprintln!("ERRO: {}", error);
+                exit(1);
+            }
+        }
+
+        match set_sources_list(ROOT_MOUNT_POINT, &system) {
+            Ok(()) => println!("O arquivo /etc/apt/sources.list foi criado com sucesso."),
+            Err(error) => {
+                eprintln!("ERRO: {}", error);
+                exit(1);
+            }
+        }
+
+        match configure_target_apt(ROOT_MOUNT_POINT, &options.apt_config, options.rootfs.proxy.as_deref()) {
+            Ok(()) => println!("A configuração do APT do novo sistema foi aplicada com sucesso."),
+            Err(error) => {
+                eprintln!("ERRO: {}", error);
+                exit(1);
+            }
+        }
+
+        let mut chroot_apt_options = match &options.rootfs.offline_repo {
+            Some(repo_dir) => {
+                match mount_offline_repository(repo_dir) {
+                    Ok(()) => println!("O repositório local foi montado no novo sistema com sucesso."),
+                    Err(error) => {
+                        eprintln!("ERRO: {}", error);
+                        exit(1);
+                    }
+                }
+
+                match write_offline_source_list(
+                    &format!("{}{}", ROOT_MOUNT_POINT, OFFLINE_SOURCE_LIST),
+                    OFFLINE_REPO_CHROOT_DIR,
+                    &options.rootfs.suite
+                ) {
+                    Ok(()) => offline_apt_options(OFFLINE_SOURCE_LIST),
+                    Err(error) => {
+                        eprintln!("ERRO: {}", error);
+                        exit(1);
+                    }
+                }
+            },
+            None => Vec::new(),
+        };
+        chroot_apt_options.extend(options.rootfs.proxy_apt_options());
+
+        match install_extra_packages(&chroot_apt_options, &options.rootfs.packages) {
+            Ok(()) => println!("Os pacotes extras foram instalados no novo sistema com sucesso."),
+            Err(error) => {
+                eprintln!("ERRO: {}", error);
+                exit(1);
+            }
+        }
+
+        if options.rootfs.offline_repo.is_some() {
+            match umount_offline_repository() {
+                Ok(()) => println!("O repositório local foi desmontado do novo sistema com sucesso."),
+                Err(error) => {
+                    eprintln!("ERRO: {}", error);
+                    exit(1);
+                }
+            }
+        }
+
+        if let Some(cache_dir) = &options.rootfs_cache_dir {
+            match store_rootfs_cache(cache_dir, &cache_key) {
+                Ok(()) => println!("O sistema de arquivos da raiz foi armazenado no cache com sucesso."),
+                Err(error) => {
+                    eprintln!("ERRO: {}", error);
+                    exit(1);
+                }
+            }
+        }
     }
-    
-    match prepare_root_filesystem() {
-        Ok(()) => println!("O sistema de arquivos da raiz foi preparado com sucesso."),
+
+    for kernel in &kernels {
+        match install_kernel_modules(kernel) {
+            Ok(()) => println!("Os módulos do kernel {} foram instalados com sucesso.", kernel.release),
+            Err(error) => {
+                eprintln!("ERRO: {}", error);
+                exit(1);
+            }
+        }
+    }
+
+
+    // CONFIGURA O SISTEMA
+
+    match set_hostname(ROOT_MOUNT_POINT, &system) {
+        Ok(()) => println!("O arquivo /etc/hostname foi criado com sucesso."),
+        Err(error) => {
+            eprintln!("ERRO: {}", error);
+            exit(1);
+        }
+    }
+
+    match set_hosts(ROOT_MOUNT_POINT, &system) {
+        Ok(()) => println!("O arquivo /etc/hosts foi criado com sucesso."),
         Err(error) => {
             eprintln!("ERRO: {}", error);
             exit(1);
         }
     }
 
-    match install_kernel_modules(kernel_path) {
-        Ok(()) => println!("Os módulos do kernel foram instalados com sucesso."),
+    match set_fstab(ROOT_MOUNT_POINT, &system) {
+        Ok(()) => println!("O arquivo /etc/fstab foi criado com sucesso."),
         Err(error) => {
             eprintln!("ERRO: {}", error);
             exit(1);
         }
     }
 
+    if let Some(board) = &options.board {
+        match configure_board(board) {
+            Ok(()) => println!("Os ajustes do aparelho {} foram aplicados com sucesso.", board.model),
+            Err(error) => {
+                eprintln!("ERRO: {}", error);
+                exit(1);
+            }
+        }
+    }
 
-    // CONFIGURA O SISTEMA
+    match set_root_password(&root_password) {
+        Ok(()) => println!("A senha do usuário root foi definida com sucesso."),
+        Err(error) => {
+            eprintln!("ERRO: {}", error);
+            exit(1);
+        }
+    }
+
+
+    // CONFIGURA O BOOT
+
+    for kernel in &kernels {
+        if let Err(error) = install_boot_files(kernel, &boot) {
+            eprintln!("ERRO: {}", error);
+            exit(1);
+        }
+    }
+
+    if !boot.scripts.is_empty() {
+        if let Err(error) = install_boot_scripts(&root_partition_path, &boot) {
+            eprintln!("ERRO: {}", error);
+            exit(1);
+        }
+    }
+
+    match install_kernel_hooks(&boot) {
+        Ok(()) => println!("Os hooks de atualização do kernel foram instalados com sucesso."),
+        Err(error) => {
+            eprintln!("ERRO: {}", error);
+            exit(1);
+        }
+    }
 
-    match create_extlinux_configuration_file(&root_partition_path) {
+    match create_extlinux_configuration_file(ROOT_MOUNT_POINT, &root_partition_path, &boot) {
         Ok(()) => println!("O arquivo /boot/extlinux/extlinux.conf foi criado com sucesso."),
         Err(error) => {
             eprintln!("ERRO: {}", error);
@@ -130,92 +532,668 @@ fn main() {
         }
     }
 
-    match set_hostname(&hostname) {
-        Ok(()) => println!("O arquivo /etc/hostname foi criado com sucesso."),
+    let saved_overlays = match save_boot_overlays(ROOT_MOUNT_POINT, &options.boot.overlays) {
+        Ok(saved_overlays) => saved_overlays,
+        Err(error) => {
+            eprintln!("ERRO: {}", error);
+            exit(1);
+        }
+    };
+
+    let boot_profile = render_boot_profile(
+        saved_board(options.board.as_ref()),
+        options.soc,
+        dtb_file,
+        options.boot_format,
+        &options.boot,
+        &saved_overlays
+    );
+
+    match set_boot_profile(ROOT_MOUNT_POINT, &boot_profile) {
+        Ok(()) => println!("O arquivo {} foi criado com sucesso.", BOOT_PROFILE),
         Err(error) => {
             eprintln!("ERRO: {}", error);
             exit(1);
         }
     }
 
-    match set_hosts(&hostname) {
-        Ok(()) => println!("O arquivo /etc/hosts foi criado com sucesso."),
+    if let Some(console) = &boot.console {
+        if let Err(error) = enable_serial_getty(console, &boot) {
+            eprintln!("ERRO: {}", error);
+            exit(1);
+        }
+    }
+
+
+    // FINALIZA INSTALAÇÃO
+    
+    match umount_root_partition() {
+        Ok(()) => println!("O dispositivo de armazenamento foi desmontado com sucesso."),
         Err(error) => {
             eprintln!("ERRO: {}", error);
             exit(1);
         }
     }
+}
 
-    match set_fstab(&storage_device_path) {
-        Ok(()) => println!("O arquivo /etc/fstab foi criado com sucesso."),
+fn build_rootfs(args: &[String]) {
+    // Gera um tarball ou squashfs da raiz sem tocar no eMMC
+    let (output_path, rootfs) = match parse_build_rootfs_options(args) {
+        Ok(result) => result,
         Err(error) => {
             eprintln!("ERRO: {}", error);
+            print_usage(&args[0]);
             exit(1);
         }
+    };
+
+    if rootfs.backend != RootfsBackend::Mmdebstrap {
+        eprintln!("ERRO: O subcomando build-rootfs requer o mmdebstrap!");
+        exit(1);
     }
 
-    match set_sources_list() {
-        Ok(()) => println!("O arquivo /etc/apt/sources.list foi criado com sucesso."),
+    match create_root_filesystem(&rootfs, &output_path) {
+        Ok(()) => println!("O arquivo {} foi gerado com sucesso.", output_path),
         Err(error) => {
             eprintln!("ERRO: {}", error);
             exit(1);
         }
     }
+}
 
-    match set_root_password(&root_password) {
-        Ok(()) => println!("A senha do usuário root foi definida com sucesso."),
+fn prepare_offline_repo(args: &[String]) {
+    // Baixa os pacotes necessários e gera os índices do repositório local
+    let (repo_dir, rootfs) = match parse_prepare_offline_repo_options(args) {
+        Ok(result) => result,
+        Err(error) => {
+            eprintln!("ERRO: {}", error);
+            print_usage(&args[0]);
+            exit(1);
+        }
+    };
+
+    match prepare_offline_repository(&repo_dir, &rootfs) {
+        Ok(()) => println!("O repositório local {} foi preparado com sucesso.", repo_dir),
         Err(error) => {
             eprintln!("ERRO: {}", error);
             exit(1);
         }
     }
+}
+
+fn select_dtb_file(
+    requested_dtb_file: &str,
+    kernel: &KernelFiles
+) -> String {
+    // Procura o DTB do aparelho em execução entre os DTBs do kernel
+    let running = read_running_board();
+    let detected_dtb_file = match &running {
+        Some(running) => match find_matching_dtb(&kernel.dtb_dir, running) {
+            Ok(detected_dtb_file) => detected_dtb_file,
+            Err(error) => {
+                eprintln!("ERRO: {}", error);
+                exit(1);
+            }
+        },
+        None => None,
+    };
 
+    if requested_dtb_file != AUTO_DTB_FILE {
+        // Apenas sugere o DTB detectado quando outro foi informado
+        if let Some(detected) = detected_dtb_file {
+            if detected.exact && detected.dtb_file != requested_dtb_file {
+                eprintln!("AVISO: O DTB {} corresponde ao aparelho em execução, mas {} foi informado.", detected.dtb_file, requested_dtb_file);
+            }
+        }
 
-    // INSTALA PACOTES EXTRAS
+        return requested_dtb_file.to_string();
+    }
 
-    match install_extra_packages() {
-        Ok(()) => println!("Os pacotes extras foram instalados no novo sistema com sucesso."),
+    match (running, detected_dtb_file) {
+        (Some(running), Some(detected)) if detected.exact => {
+            println!(
+                "O DTB {} foi detectado com sucesso ({}).",
+                detected.dtb_file,
+                running.model.unwrap_or(running.compatible.join(", "))
+            );
+            detected.dtb_file
+        },
+        (Some(running), Some(detected)) => {
+            // Um DTB de outra placa com o mesmo SoC pode não iniciar o aparelho
+            eprintln!(
+                "ERRO: Nenhum DTB do kernel {} corresponde ao aparelho em execução ({})! O DTB {} é apenas do mesmo SoC; informe-o explicitamente se for o correto.",
+                kernel.release,
+                running.model.unwrap_or(running.compatible.join(", ")),
+                detected.dtb_file
+            );
+            exit(1);
+        },
+        (Some(running), None) => {
+            eprintln!("ERRO: Nenhum DTB do kernel {} é compatível com o aparelho em execução ({})! Informe o DTB.", kernel.release, running.compatible.join(", "));
+            exit(1);
+        },
+        (None, _) => {
+            eprintln!("ERRO: Não foi possível ler o /proc/device-tree para detectar o aparelho! Informe o DTB.");
+            exit(1);
+        },
+    }
+}
+
+fn check_dtb(
+    kernel: &KernelFiles,
+    dtb_file: &str
+) {
+    // Verifica o DTB escolhido antes de apagar o eMMC
+    let dtb_path = format!("{}/{}", kernel.dtb_dir, dtb_file);
+
+    match read_device_tree(&dtb_path) {
+        Ok(device_tree) => println!(
+            "O DTB {} do kernel {} foi verificado ({}; {}).",
+            dtb_file,
+            kernel.release,
+            device_tree.model.unwrap_or("sem modelo".to_string()),
+            device_tree.compatible.join(", ")
+        ),
+        Err(_) if std::fs::metadata(&dtb_path).is_err() => {
+            eprintln!("ERRO: O arquivo DTB {} não existe no kernel {}!", dtb_file, kernel.release);
+            exit(1);
+        },
         Err(error) => {
             eprintln!("ERRO: {}", error);
             exit(1);
         }
     }
+}
 
+fn check_overlays(
+    kernel: &KernelFiles,
+    overlays: &[String]
+) {
+    // Verifica os overlays antes de apagar o eMMC
+    if overlays.is_empty() {
+        return;
+    }
 
-    // CONFIGURA O BOOT
+    match resolve_overlay_files(kernel, overlays) {
+        Ok(_) => println!("Os overlays do kernel {} foram verificados ({}).", kernel.release, overlays.join(", ")),
+        Err(error) => {
+            eprintln!("ERRO: {}", error);
+            exit(1);
+        }
+    }
+}
+
+fn check_bootloader(
+    files: &BootloaderFiles,
+    soc: Option<&SocProfile>
+) -> Vec<BootloaderImage> {
+    // Só os SoCs Rockchip usam a área do carregador no início do eMMC. Em
+    // outros SoCs, como os Allwinner, os setores gravados pertencem ao
+    // carregador de fábrica
+    match soc {
+        Some(soc) if soc.rockchip_bootloader => {},
+        Some(soc) => {
+            eprintln!("ERRO: O SoC {} não usa o carregador da Rockchip!", soc.name);
+            exit(1);
+        },
+        None => {
+            eprintln!("ERRO: Informe um SoC Rockchip com --soc ou --board para gravar o carregador!");
+            exit(1);
+        },
+    }
+
+    match load_bootloader_images(files) {
+        Ok(images) => {
+            for image in &images {
+                println!("O arquivo {} foi verificado (setores {} a {}).", image.filepath, image.sector, image.end_sector() - 1);
+            }
 
-    match copy_boot_files(kernel_path, kernel_release) {
-        Ok(()) => println!("Os arquivos de boot foram copiados com sucesso."),
+            images
+        },
         Err(error) => {
             eprintln!("ERRO: {}", error);
             exit(1);
         }
     }
+}
+
+fn check_kernel(
+    kernel: &KernelFiles,
+    dtb_file: &str,
+    board: Option<&Board>,
+    ignore_kernel_config: bool
+) {
+    // Verifica a configuração do kernel antes de apagar o eMMC
+    let requirements = KernelRequirements {
+        dtb_file: dtb_file.to_string(),
+        board_options: board.map(|board| board.kernel_config.clone()).unwrap_or_default(),
+    };
 
-    match generate_boot_images(kernel_release) {
-        Ok(()) => println!("As imagens de boot foram geradas com sucesso."),
+    match check_kernel_config(&kernel.config, &requirements) {
+        Ok(issues) => {
+            let mut errors = 0;
+
+            for issue in &issues {
+                match issue.severity {
+                    Severity::Error => {
+                        errors += 1;
+                        eprintln!("ERRO: A opção {} não está habilitada no kernel {} ({}).", issue.option, kernel.release, issue.reason);
+                    },
+                    Severity::Warning => {
+                        eprintln!("AVISO: A opção {} não está habilitada no kernel {} ({}).", issue.option, kernel.release, issue.reason);
+                    },
+                }
+            }
+
+            if errors > 0 && !ignore_kernel_config {
+                eprintln!("ERRO: A configuração do kernel não atende aos requisitos! Use --ignore-kernel-config para continuar mesmo assim.");
+                exit(1);
+            }
+
+            println!("A configuração do kernel {} foi verificada.", kernel.release);
+        },
         Err(error) => {
             eprintln!("ERRO: {}", error);
             exit(1);
         }
     }
+}
+
+fn install_boot_files(
+    kernel: &KernelFiles,
+    boot: &BootConfig
+) -> Result<(), std::io::Error> {
+    // Copia os arquivos de boot, gera as imagens e atualiza os links
+    copy_boot_files(kernel)?;
+    println!("Os arquivos de boot do kernel {} foram copiados com sucesso.", kernel.release);
+
+    if !boot.overlays.is_empty() {
+        install_overlays(kernel, boot)?;
+        println!("Os overlays do kernel {} foram instalados com sucesso.", kernel.release);
+    }
+
+    generate_boot_images(kernel, boot)?;
+    println!("As imagens de boot do kernel {} foram geradas com sucesso.", kernel.release);
+
+    create_boot_symbolic_links(kernel, &boot.dtb_file)?;
+    println!("Os links simbólicos do kernel {} foram criados com sucesso.", kernel.release);
+
+    if boot.boot_format == BootFormat::Fit {
+        create_fit_image(kernel, boot)?;
+        println!("A imagem FIT do kernel {} foi gerada com sucesso.", kernel.release);
+    }
+
+    Ok(())
+}
+
+fn install_boot_scripts(
+    root_partition_path: &str,
+    boot: &BootConfig
+) -> Result<(), std::io::Error> {
+    // Cria os scripts para os U-Boots que não leem o extlinux.conf
+    create_boot_scripts(root_partition_path, boot)?;
+    println!("Os scripts de boot foram criados com sucesso ({}).", boot.scripts.join(", "));
+
+    Ok(())
+}
+
+fn enable_serial_getty(
+    console: &str,
+    boot: &BootConfig
+) -> Result<(), std::io::Error> {
+    // Habilita o login pela console serial escolhida
+    configure_serial_getty(boot)?;
+    println!("O getty da console serial {} foi habilitado com sucesso.", console);
+
+    Ok(())
+}
+
+fn saved_board(board: Option<&Board>) -> Option<&Board> {
+    // Os aparelhos de --boards-file não estão disponíveis no sistema
+    // instalado; para eles ficam salvos apenas o SoC e o DTB
+    let board = board?;
+
+    match find_board(&board.name, &[]) {
+        Ok(embedded) if embedded.soc.name == board.soc.name && embedded.dtb_file == board.dtb_file => Some(board),
+        _ => None,
+    }
+}
+
+fn exit_unmounting(error: std::io::Error) -> ! {
+    // Desmonta a instalação antes de encerrar o update-kernel com erro
+    eprintln!("ERRO: {}", error);
+
+    if let Err(error) = umount_root_partition() {
+        eprintln!("AVISO: {}", error);
+    }
+
+    exit(1);
+}
+
+fn update_kernel(args: &[String]) {
+    // Instala um novo kernel em um aparelho já instalado, mantendo o
+    // anterior como alternativa no menu de boot
+    let options = match parse_update_kernel_options(args) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("ERRO: {}", error);
+            print_usage(&args[0]);
+            exit(1);
+        }
+    };
 
-    match create_boot_symbolic_links(kernel_release, dtb_file) {
-        Ok(()) => println!("Os links simbólicos foram criados com sucesso."),
+    let root_partition_path = match mount_installed_system(&options.target) {
+        Ok(root_partition_path) => {
+            println!("A instalação em {} foi montada com sucesso.", root_partition_path);
+            root_partition_path
+        },
         Err(error) => {
             eprintln!("ERRO: {}", error);
             exit(1);
         }
+    };
+
+    // As configurações de boot salvas na instalação valem como padrão, e as
+    // opções da linha de comando prevalecem sobre elas
+    let boot_profile = format!("{}{}", ROOT_MOUNT_POINT, BOOT_PROFILE);
+
+    let options = if Path::new(&boot_profile).exists() {
+        let mut saved_args = args[..2].to_vec();
+        saved_args.push("--profile".to_string());
+        saved_args.push(boot_profile);
+        saved_args.extend_from_slice(&args[2..]);
+
+        match parse_update_kernel_options(&saved_args) {
+            Ok(mut options) => {
+                for overlay in options.boot.overlays.iter_mut() {
+                    *overlay = saved_overlay_path(ROOT_MOUNT_POINT, overlay);
+                }
+
+                println!("As configurações de boot da instalação foram carregadas com sucesso.");
+                options
+            },
+            Err(error) => exit_unmounting(error),
+        }
+    } else if options.soc.is_none() {
+        // Sem o SoC o endereço de carga, a console e os hooks seriam os padrões
+        exit_unmounting(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("A instalação não tem o arquivo {}! Informe --soc ou --board.", BOOT_PROFILE)
+        ));
+    } else {
+        options
+    };
+
+    let kernel_source = KernelSource::detect(&options.kernel);
+
+    let rootfs = RootfsOptions {
+        proxy: options.apt_proxy.clone(),
+        ..RootfsOptions::default()
+    };
+    let host_apt_options = rootfs.proxy_apt_options();
+
+    let dependencies: Vec<&str> = kernel_source.dependencies()
+        .iter()
+        .chain(options.boot_format.dependencies())
+        .chain(overlay_dependencies(&options.boot.overlays, options.boot.merge_overlays))
+        .copied()
+        .collect();
+
+    match install_dependencies(&host_apt_options, &dependencies) {
+        Ok(()) => println!("As dependências do instalador foram instaladas com sucesso."),
+        Err(error) => exit_unmounting(error),
+    }
+
+    // A arquitetura do sistema instalado vem do SoC, quando informado
+    let architecture = options.soc.map(|soc| soc.architecture.debian_architecture());
+
+    let kernel = match kernel_source.prepare(&host_apt_options, architecture, KERNEL_STAGING_DIR) {
+        Ok(kernel) => {
+            println!("A versão do kernel foi obtida com sucesso ({}).", kernel.release);
+            kernel
+        },
+        Err(error) => exit_unmounting(error),
+    };
+
+    let dtb_file = select_dtb_file(&options.dtb_file, &kernel);
+    let dtb_file = dtb_file.as_str();
+
+    check_dtb(&kernel, dtb_file);
+    check_overlays(&kernel, &options.boot.overlays);
+    check_kernel(&kernel, dtb_file, options.board.as_ref(), options.ignore_kernel_config);
+
+    let boot = match create_boot_config(
+        kernel.architecture,
+        options.soc,
+        dtb_file,
+        options.boot_format,
+        &options.boot
+    ) {
+        Ok(boot) => boot,
+        Err(error) => exit_unmounting(error),
+    };
+
+    match install_kernel_modules(&kernel) {
+        Ok(()) => println!("Os módulos do kernel {} foram instalados com sucesso.", kernel.release),
+        Err(error) => exit_unmounting(error),
+    }
+
+    if let Err(error) = install_boot_files(&kernel, &boot) {
+        exit_unmounting(error);
+    }
+
+    if !boot.scripts.is_empty() {
+        if let Err(error) = install_boot_scripts(&root_partition_path, &boot) {
+            exit_unmounting(error);
+        }
     }
 
+    match install_kernel_hooks(&boot) {
+        Ok(()) => println!("Os hooks de atualização do kernel foram instalados com sucesso."),
+        Err(error) => exit_unmounting(error),
+    }
+
+    // O extlinux.conf é gerado novamente, então as edições manuais ficam
+    // apenas na cópia
+    match backup_extlinux_configuration() {
+        Ok(()) => println!("O arquivo /boot/extlinux/extlinux.conf foi copiado para {} com sucesso.", EXTLINUX_BACKUP),
+        Err(error) => exit_unmounting(error),
+    }
+
+    match create_extlinux_configuration_file(ROOT_MOUNT_POINT, &root_partition_path, &boot) {
+        Ok(()) => println!("O arquivo /boot/extlinux/extlinux.conf foi atualizado com sucesso."),
+        Err(error) => exit_unmounting(error),
+    }
+
+    // Mantém salvas as configurações usadas nesta atualização
+    let saved_overlays = match save_boot_overlays(ROOT_MOUNT_POINT, &options.boot.overlays) {
+        Ok(saved_overlays) => saved_overlays,
+        Err(error) => exit_unmounting(error),
+    };
+
+    let boot_profile = render_boot_profile(
+        saved_board(options.board.as_ref()),
+        options.soc,
+        dtb_file,
+        options.boot_format,
+        &options.boot,
+        &saved_overlays
+    );
+
+    match set_boot_profile(ROOT_MOUNT_POINT, &boot_profile) {
+        Ok(()) => println!("O arquivo {} foi atualizado com sucesso.", BOOT_PROFILE),
+        Err(error) => exit_unmounting(error),
+    }
+
+    if let Some(console) = &boot.console {
+        if let Err(error) = enable_serial_getty(console, &boot) {
+            exit_unmounting(error);
+        }
+    }
 
-    // FINALIZA INSTALAÇÃO
-    
     match umount_root_partition() {
-        Ok(()) => println!("O dispositivo de armazenamento foi desmontado com sucesso."),
+        Ok(()) => println!("A instalação foi desmontada com sucesso."),
+        Err(error) => {
+            eprintln!("ERRO: {}", error);
+            exit(1);
+        }
+    }
+}
+
+fn inspect_uimage(args: &[String]) {
+    // Exibe e valida o cabeçalho de uma imagem uImage ou uInitrd existente
+    if args.len() != 3 {
+        eprintln!("ERRO: Número de argumentos incorreto!");
+        print_usage(&args[0]);
+        exit(1);
+    }
+
+    match read_uimage_header(&args[2]) {
+        Ok((header, data_size)) => {
+            println!("Nome:         {}", header.name);
+            println!("Sistema:      {}", header.os.name());
+            println!("Arquitetura:  {}", header.architecture.name());
+            println!("Tipo:         {}", header.image_type.name());
+            println!("Compressão:   {}", header.compression.name());
+            println!("Carga:        {:#010x}", header.load_address);
+            println!("Entrada:      {:#010x}", header.entry_point);
+            println!("Data:         {}", header.timestamp);
+            println!("Tamanho:      {} bytes", data_size);
+            println!("Os CRCs do cabeçalho e dos dados são válidos.");
+        },
+        Err(error) => {
+            eprintln!("ERRO: {}", error);
+            exit(1);
+        }
+    }
+}
+
+fn list_boards(args: &[String]) {
+    // Lista os aparelhos da base embutida e dos arquivos informados
+    let boards = match parse_list_boards_options(args).and_then(|boards_files| load_boards(&boards_files)) {
+        Ok(boards) => boards,
+        Err(error) => {
+            eprintln!("ERRO: {}", error);
+            exit(1);
+        }
+    };
+
+    for board in &boards {
+        println!("{:<16} {:<24} {:<8} {}", board.name, board.model, board.soc.name, board.dtb_file);
+    }
+}
+
+fn inspect_dtb(args: &[String]) {
+    // Exibe e valida um arquivo DTB
+    if args.len() != 3 {
+        eprintln!("ERRO: Número de argumentos incorreto!");
+        print_usage(&args[0]);
+        exit(1);
+    }
+
+    match read_device_tree(&args[2]) {
+        Ok(device_tree) => {
+            println!("Modelo:       {}", device_tree.model.unwrap_or("-".to_string()));
+            println!("Compatível:   {}", device_tree.compatible.join(", "));
+            println!("Versão:       {}", device_tree.version);
+            println!("Tamanho:      {} bytes", device_tree.total_size);
+            println!("Nós:          {}", device_tree.node_count);
+            println!("Propriedades: {}", device_tree.property_count);
+            println!("O arquivo é um device tree válido.");
+        },
         Err(error) => {
             eprintln!("ERRO: {}", error);
             exit(1);
         }
     }
+}
+
+fn verify(args: &[String]) {
+    // Confere a cadeia de boot de uma instalação existente
+    if args.len() != 3 {
+        eprintln!("ERRO: Número de argumentos incorreto!");
+        print_usage(&args[0]);
+        exit(1);
+    }
+
+    let root_partition_path = match mount_installed_system(&args[2]) {
+        Ok(root_partition_path) => {
+            println!("A instalação em {} foi montada com sucesso.", root_partition_path);
+            root_partition_path
+        },
+        Err(error) => {
+            eprintln!("ERRO: {}", error);
+            exit(1);
+        }
+    };
+
+    let issues = verify_installation(ROOT_MOUNT_POINT, &root_partition_path);
+
+    match umount_root_partition() {
+        Ok(()) => println!("A instalação foi desmontada com sucesso."),
+        Err(error) => {
+            eprintln!("ERRO: {}", error);
+            exit(1);
+        }
+    }
+
+    let mut errors = 0;
+
+    for issue in &issues {
+        match issue.severity {
+            Severity::Error => {
+                errors += 1;
+                eprintln!("ERRO: {}: {}", issue.subject, issue.message);
+            },
+            Severity::Warning => eprintln!("AVISO: {}: {}", issue.subject, issue.message),
+        }
+    }
+
+    if errors > 0 {
+        eprintln!("ERRO: Foram encontrados {} problemas na cadeia de boot!", errors);
+        exit(1);
+    }
+
+    println!("A cadeia de boot foi verificada com sucesso.");
+}
+
+fn test_boot(args: &[String]) {
+    // Inicia a imagem no QEMU e espera o sistema chegar ao login
+    let options = match parse_test_boot_options(args) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("ERRO: {}", error);
+            print_usage(&args[0]);
+            exit(1);
+        }
+    };
+
+    let result = match run_boot_test(&options) {
+        Ok(result) => result,
+        Err(error) => {
+            eprintln!("ERRO: {}", error);
+            exit(1);
+        }
+    };
+
+    if let Some(log) = &options.log {
+        println!("O log da console foi gravado em {}.", log);
+    }
+
+    if result.passed {
+        println!("O teste de boot passou. {}", result.reason);
+        return;
+    }
+
+    // Sem um arquivo de log, mostra o final da console para o diagnóstico
+    if options.log.is_none() {
+        let lines: Vec<&str> = result.log.lines().collect();
+
+        for line in &lines[lines.len().saturating_sub(TEST_BOOT_LOG_LINES)..] {
+            eprintln!("{}", line);
+        }
+    }
+
+    eprintln!("ERRO: O teste de boot falhou! {}", result.reason);
+    exit(1);
 }
\ No newline at end of file
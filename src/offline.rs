@@ -0,0 +1,246 @@
+use std::fs;
+use std::process::Command;
+
+use crate::configure_boot::*;
+use crate::constants::*;
+use crate::install::*;
+
+pub fn prepare_offline_repository(
+    repo_dir: &str,
+    rootfs: &RootfsOptions
+) -> Result<(), std::io::Error> {
+    // Cria o diretório dos pacotes
+    let pool_dir = format!("{}/pool", repo_dir);
+
+    if let Err(_) = fs::create_dir_all(pool_dir.as_str()) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao criar o diretório {}!", pool_dir)
+        ));
+    }
+
+    // Baixa os pacotes da raiz, os pacotes extras e as dependências do
+    // instalador usando uma raiz descartável do mmdebstrap. Os pacotes
+    // essenciais são mantidos no cache para que o pool os contenha
+    let mut packages = rootfs.packages.packages();
+    packages.extend(DEPENDENCY_PACKAGES.iter().map(|package| package.to_string()));
+
+    // O formato de boot só é conhecido na instalação, então o repositório
+    // inclui também os pacotes usados pelo FIT e pelos overlays
+    packages.extend(BootFormat::Fit.dependencies().iter().map(|package| package.to_string()));
+
+    let output = Command::new("mmdebstrap")
+        .arg(format!("--architectures={}", rootfs.architecture()))
+        .arg("--mode=auto")
+        .arg(format!("--include={}", packages.join(",")))
+        .arg("--skip=essential/unlink")
+        .arg(format!("--customize-hook=sync-out /var/cache/apt/archives {}", pool_dir))
+        .arg(&rootfs.suite)
+        .arg("/dev/null")
+        .args(rootfs.mirrors())
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Falha ao baixar os pacotes do repositório local!"
+        ));
+    }
+
+    generate_repository_indices(repo_dir, &rootfs.suite, rootfs.architecture())
+}
+
+fn generate_repository_indices(
+    repo_dir: &str,
+    suite: &str,
+    architecture: &str
+) -> Result<(), std::io::Error> {
+    // Cria o caminho dists/<suite>/main/binary-<arquitetura>
+    let suite_dir = format!("dists/{}", suite);
+    let packages_dir = format!("{}/main/binary-{}", suite_dir, architecture);
+
+    if let Err(_) = fs::create_dir_all(format!("{}/{}", repo_dir, packages_dir)) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao criar o caminho {}!", packages_dir)
+        ));
+    }
+
+    // Gera o índice Packages
+    let output = Command::new("apt-ftparchive")
+        .current_dir(repo_dir)
+        .arg("packages")
+        .arg("pool")
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Falha ao gerar o índice Packages do repositório local!"
+        ));
+    }
+
+    let filepath = format!("{}/{}/Packages", repo_dir, packages_dir);
+
+    if let Err(_) = fs::write(filepath, output.stdout) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Falha ao criar o índice Packages do repositório local!"
+        ));
+    }
+
+    // Gera o arquivo Release
+    let output = Command::new("apt-ftparchive")
+        .current_dir(repo_dir)
+        .arg("-o")
+        .arg(format!("APT::FTPArchive::Release::Suite={}", suite))
+        .arg("-o")
+        .arg(format!("APT::FTPArchive::Release::Codename={}", suite))
+        .arg("-o")
+        .arg(format!("APT::FTPArchive::Release::Architectures={}", architecture))
+        .arg("-o")
+        .arg("APT::FTPArchive::Release::Components=main")
+        .arg("release")
+        .arg(&suite_dir)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Falha ao gerar o arquivo Release do repositório local!"
+        ));
+    }
+
+    let filepath = format!("{}/{}/Release", repo_dir, suite_dir);
+
+    if let Err(_) = fs::write(filepath, output.stdout) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Falha ao criar o arquivo Release do repositório local!"
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn check_offline_architecture(
+    architecture: &str
+) -> Result<(), std::io::Error> {
+    // O repositório local só tem pacotes da arquitetura do novo sistema,
+    // então as dependências do instalador só podem vir dele quando a
+    // máquina tem a mesma arquitetura
+    let output = Command::new("dpkg")
+        .arg("--print-architecture")
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Falha ao obter a arquitetura desta máquina!"
+        ));
+    }
+
+    let host_architecture = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    if host_architecture != architecture {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!(
+                "O modo offline exige que esta máquina ({}) tenha a mesma arquitetura do novo sistema ({})!",
+                host_architecture,
+                architecture
+            )
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn write_offline_source_list(
+    source_list_path: &str,
+    repo_dir: &str,
+    suite: &str
+) -> Result<(), std::io::Error> {
+    // Cria uma lista de fontes contendo apenas o repositório local
+    let source_list = format!("deb [trusted=yes] file:{} {} main\n", repo_dir, suite);
+
+    if let Err(_) = fs::write(source_list_path, source_list) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao criar o arquivo {}!", source_list_path)
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn offline_apt_options(source_list_path: &str) -> Vec<String> {
+    // Faz o APT ignorar as fontes configuradas no sistema
+    vec![
+        "-o".to_string(),
+        format!("Dir::Etc::SourceList={}", source_list_path),
+        "-o".to_string(),
+        "Dir::Etc::SourceParts=-".to_string(),
+    ]
+}
+
+pub fn mount_offline_repository(repo_dir: &str) -> Result<(), std::io::Error> {
+    // Cria o ponto de montagem do repositório local no novo sistema
+    let mount_point = format!("{}{}", ROOT_MOUNT_POINT, OFFLINE_REPO_CHROOT_DIR);
+
+    if let Err(_) = fs::create_dir_all(mount_point.as_str()) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Falha ao criar o ponto de montagem do repositório local!"
+        ));
+    }
+
+    // Monta o repositório local dentro do novo sistema
+    let output = Command::new("mount")
+        .arg("--bind")
+        .arg(repo_dir)
+        .arg(mount_point)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Falha ao montar o repositório local no novo sistema!"
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn umount_offline_repository() -> Result<(), std::io::Error> {
+    // Desmonta o repositório local do novo sistema
+    let mount_point = format!("{}{}", ROOT_MOUNT_POINT, OFFLINE_REPO_CHROOT_DIR);
+
+    let output = Command::new("umount")
+        .arg(&mount_point)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Falha ao desmontar o repositório local do novo sistema!"
+        ));
+    }
+
+    if let Err(_) = fs::remove_dir(mount_point) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Falha ao remover o ponto de montagem do repositório local!"
+        ));
+    }
+
+    // Remove a lista de fontes temporária do novo sistema
+    if let Err(_) = fs::remove_file(format!("{}{}", ROOT_MOUNT_POINT, OFFLINE_SOURCE_LIST)) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao remover o arquivo {}!", OFFLINE_SOURCE_LIST)
+        ));
+    }
+
+    Ok(())
+}
\ No newline at end of file
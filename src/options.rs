@@ -0,0 +1,750 @@
+use std::fs;
+
+use crate::apt_config::*;
+use crate::boards::*;
+use crate::boot_script::*;
+use crate::bootloader::*;
+use crate::configure_boot::*;
+use crate::constants::*;
+use crate::install::*;
+use crate::kernel::*;
+use crate::overlays::*;
+use crate::package_sets::*;
+use crate::profile::*;
+use crate::soc_profiles::*;
+
+const FLAG_OPTIONS: &[&str] = &["--keep-apt-proxy", "--ignore-kernel-config", "--merge-overlays"];
+
+// Opções de boot comuns à instalação e ao update-kernel
+const BOOT_OPTIONS: &[&str] = &[
+    "--overlay",
+    "--console",
+    "--loglevel",
+    "--kernel-args",
+    "--remove-kernel-args",
+    "--boot-scripts",
+    "--boot-timeout",
+];
+
+pub struct InstallOptions {
+    pub storage_device_path: String,
+    pub kernel: String,
+    pub dtb_file: String,
+    pub rootfs: RootfsOptions,
+    pub rootfs_cache_dir: Option<String>,
+    pub security_mirror: Option<String>,
+    pub apt_config: AptConfig,
+    pub ignore_kernel_config: bool,
+    pub extra_kernels: Vec<String>,
+    pub board: Option<Board>,
+    pub soc: Option<&'static SocProfile>,
+    pub boot_format: BootFormat,
+    pub boot: BootOptions,
+    pub bootloader: BootloaderFiles,
+}
+
+pub struct UpdateKernelOptions {
+    pub target: String,
+    pub kernel: String,
+    pub dtb_file: String,
+    pub apt_proxy: Option<String>,
+    pub ignore_kernel_config: bool,
+    pub board: Option<Board>,
+    pub soc: Option<&'static SocProfile>,
+    pub boot_format: BootFormat,
+    pub boot: BootOptions,
+}
+
+pub fn print_usage(program: &str) {
+    eprintln!("\nUso: {} <emmc> <kernel> <dtb> [opções]", program);
+    eprintln!("     {} build-rootfs <saída> [opções]", program);
+    eprintln!("     {} prepare-offline-repo <repositório> [opções]", program);
+    eprintln!("     {} update-kernel <raiz> <kernel> <dtb> [opções]", program);
+    eprintln!("     {} inspect-uimage <imagem>", program);
+    eprintln!("     {} inspect-dtb <arquivo.dtb>", program);
+    eprintln!("     {} verify <raiz>", program);
+    eprintln!("     {} test-boot <kernel> <disco> [opções de teste]", program);
+    eprintln!("     {} list-boards [--boards-file <arquivo>]\n\nOnde:\n", program);
+    eprintln!("  <emmc>    Caminho para dispositivo eMMC.\n            Exemplo: /dev/mmcblk0\n");
+    eprintln!("  <kernel>  Kernel Linux a ser instalado. Pode ser o diretório do código fonte compilado, um pacote\n            linux-image-*.deb (make bindeb-pkg), um tarball com zImage, dtbs/ e lib/modules/ ou o nome\n            de um pacote do repositório.\n            Exemplo: /mnt/pendrive/linux-6.1.57\n");
+    eprintln!("  <dtb>     Nome do arquivo DTB do kernel que deve ser usado, incluindo o subdiretório do\n            fabricante quando houver. Use auto, ou omita, para detectar pelo /proc/device-tree do\n            aparelho em execução.\n            Exemplo: rk322x-box.dtb ou rockchip/rk3328-box.dtb\n");
+    eprintln!("  <saída>   Arquivo gerado pelo mmdebstrap (.tar, .tar.gz, .tar.xz ou .squashfs).\n            Exemplo: /srv/imagens/bookworm-armhf.tar.xz\n");
+    eprintln!("  <repositório>  Diretório onde o repositório local de pacotes será criado.\n                 Exemplo: /mnt/pendrive/repositorio\n");
+    eprintln!("  <raiz>    Partição raiz de um aparelho já instalado, ou / para o sistema em execução.\n            O extlinux.conf é gerado novamente; as edições manuais ficam em\n            /boot/extlinux/extlinux.conf.bak. As opções de boot da instalação, salvas em\n            /etc/installer/boot.profile, são usadas como padrão; o <dtb> também pode ser\n            informado com --dtb <arquivo>.\n            Exemplo: /dev/mmcblk0p1\n");
+    eprintln!("  <imagem>  Imagem legada do U-Boot (uImage ou uInitrd) a ser validada.\n            Exemplo: /boot/uImage\n");
+    eprintln!("Opções:\n");
+    eprintln!("  --profile <arquivo>      Perfil com opções no formato <opção> = <valor>, sem os traços.\n                           Exemplo: package-set = server");
+    eprintln!("  --rootfs-backend <nome>  Ferramenta usada para criar a raiz: debootstrap (padrão) ou mmdebstrap.");
+    eprintln!("  --suite <nome>           Versão do Debian a ser instalada. Padrão: bookworm.");
+    eprintln!("  --architecture <nome>    Arquitetura do Debian. Padrão: a do kernel instalado (armhf ou arm64).");
+    eprintln!("  --mirror <url>           Espelho do Debian. Pode ser repetido com mmdebstrap.");
+    eprintln!("  --security-mirror <url>  Repositório de segurança do novo sistema. Padrão: o do espelho com o\n                           sufixo -security, como http://deb.debian.org/debian-security/.\n                           Exemplo: http://security.debian.org/debian-security");
+    eprintln!("  --hook <fase>:<comando>  Hook do mmdebstrap (setup, extract, essential ou customize).");
+    eprintln!("  --package-set <nome>     Conjunto de pacotes: minimal, server (padrão), desktop-lite ou kiosk.");
+    eprintln!("  --add-packages <lista>   Pacotes instalados além do conjunto escolhido.");
+    eprintln!("  --remove-packages <lista> Pacotes retirados do conjunto e removidos do sistema base. Os pacotes\n                           usados no boot (u-boot-tools, initramfs-tools, systemd...) são recusados.");
+    eprintln!("  --enable-units <lista>   Unidades do systemd a habilitar.");
+    eprintln!("  --disable-units <lista>  Unidades do systemd a desabilitar.");
+    eprintln!("  --mask-units <lista>     Unidades do systemd a mascarar.");
+    eprintln!("  --offline-repo <dir>     Instala sem internet a partir de um repositório local criado com\n                           prepare-offline-repo. Esta máquina deve ter a arquitetura\n                           do novo sistema.");
+    eprintln!("  --apt-proxy <url>        Proxy HTTP usado pelo APT durante a instalação.\n                           Exemplo: http://192.168.0.10:3142");
+    eprintln!("  --keep-apt-proxy         Mantém o proxy configurado no novo sistema.");
+    eprintln!("  --apt-conf <arquivo>     Trecho copiado para /etc/apt/apt.conf.d. Pode ser repetido.");
+    eprintln!("  --apt-repository <linha> Repositório extra adicionado às fontes do novo sistema.\n                           Exemplo: \"deb [signed-by=/etc/apt/keyrings/extra.gpg] http://... bookworm main\"");
+    eprintln!("  --apt-keyring <arquivo>  Chaveiro copiado para /etc/apt/keyrings. Pode ser repetido.");
+    eprintln!("  --extra-kernel <kernel>  Kernel adicional instalado lado a lado com o principal. Pode ser repetido.");
+    eprintln!("  --idbloader <arquivo>    idbloader.img do U-Boot mainline, gravado no setor 64 do eMMC (Rockchip).\n                           Usado junto com --uboot-itb.");
+    eprintln!("  --uboot-itb <arquivo>    u-boot.itb do U-Boot mainline, gravado no setor 16384 do eMMC (Rockchip).");
+    eprintln!("  --uboot-rockchip <arquivo> u-boot-rockchip.bin, com o idbloader e o u-boot.itb, gravado no setor 64\n                           do eMMC (Rockchip). A partição raiz passa a começar em 16 MiB. Estas\n                           opções exigem um SoC Rockchip em --soc ou --board.");
+    eprintln!(
+        "  --soc <nome>             SoC do aparelho, que define o endereço de carga, a console e os parâmetros\n                           de boot: {}. Com esta opção o <dtb> pode ser omitido.",
+        SOC_PROFILES.iter().map(|profile| profile.name).collect::<Vec<&str>>().join(", ")
+    );
+    eprintln!("  --board <nome>           Aparelho da base de aparelhos (veja list-boards), que define o SoC, o DTB,\n                           o firmware do Wi-Fi e os ajustes de LEDs e controle remoto. Com esta\n                           opção o <dtb> pode ser omitido.\n                           Exemplo: h96-mini");
+    eprintln!("  --boards-file <arquivo>  Arquivo com definições adicionais de aparelhos. Pode ser repetido.");
+    eprintln!("  --boot-format <nome>     Formato das imagens de boot: legacy (uImage) ou fit (image.itb com\n                           boot.scr). Padrão: o do SoC, ou legacy.");
+    eprintln!("  --overlay <dtbo>         Overlay do device tree (arquivo .dtbo ou nome de um overlay do kernel)\n                           copiado para /boot/dtb/overlays e aplicado pelo FDTOVERLAYS do extlinux.conf.\n                           Pode ser repetido.\n                           Exemplo: rk3328-uart1");
+    eprintln!("  --merge-overlays         Aplica os overlays ao DTB na instalação, para versões antigas do U-Boot.\n                           Sempre usado com --boot-format fit.");
+    eprintln!("  --console <tty>          Console serial do kernel, onde também é habilitado um getty. Use none\n                           para não definir a console. Padrão: a do SoC.\n                           Exemplo: ttyS2,1500000n8");
+    eprintln!("  --loglevel <nível>       Nível de mensagens do kernel na console (0 a 7). Padrão: 0.");
+    eprintln!("  --kernel-args <args>     Parâmetros adicionais do kernel, que substituem os padrões com o mesmo\n                           nome, exceto console=, que é acrescentado. Pode ser repetido.\n                           Exemplo: \"cma=256M mitigations=off quiet splash\"");
+    eprintln!("  --remove-kernel-args <lista> Parâmetros padrão retirados da linha de comando do kernel.\n                           Exemplo: earlyprintk,earlycon");
+    eprintln!("  --boot-scripts <lista>   Scripts do U-Boot gerados para aparelhos sem suporte ao extlinux.conf:\n                           boot.scr, s905_autoscript e aml_autoscript, ou none. Padrão: os do SoC.");
+    eprintln!("  --boot-timeout <seg>     Tempo de espera do menu de boot em segundos, até 600. Padrão: 3.");
+    eprintln!("  --ignore-kernel-config   Continua mesmo que faltem opções obrigatórias na configuração do kernel.");
+    eprintln!("  --rootfs-cache <dir>     Reaproveita a raiz com os pacotes já instalados a partir de um cache.\n                           Exemplo: /var/cache/installer/rootfs\n");
+    eprintln!("Opções de teste (test-boot):\n");
+    eprintln!("  <kernel>  Kernel de teste com suporte à máquina virt do QEMU (Image ou zImage).");
+    eprintln!("  <disco>   Imagem gerada, com tabela de partição, ou imagem do sistema de arquivos da raiz.\n            Não é alterada pelo teste.\n");
+    eprintln!("  --initrd <arquivo>       Initrd carregado junto com o kernel.");
+    eprintln!("  --architecture <nome>    arm ou arm64. Padrão: detectada pela imagem do kernel.");
+    eprintln!("  --root <dispositivo>     Raiz no QEMU. Padrão: /dev/vda1, ou /dev/vda sem tabela de partição.");
+    eprintln!("  --kernel-args <args>     Parâmetros adicionais do kernel.");
+    eprintln!("  --marker <texto>         Texto da console que indica o sucesso do boot. Padrão: login:");
+    eprintln!("  --timeout <seg>          Tempo limite para o marcador aparecer. Padrão: 300.");
+    eprintln!("  --memory <MB>            Memória da máquina virtual. Padrão: 1024.");
+    eprintln!("  --log <arquivo>          Grava o log da console serial.\n");
+}
+
+pub struct BootTestOptions {
+    pub kernel: String,
+    pub disk: String,
+    pub initrd: Option<String>,
+    pub architecture: Option<KernelArchitecture>,
+    pub root: Option<String>,
+    pub extra_args: Vec<String>,
+    pub marker: String,
+    pub timeout: u32,
+    pub memory: u32,
+    pub log: Option<String>,
+}
+
+pub fn parse_install_options(
+    args: &[String]
+) -> Result<InstallOptions, std::io::Error> {
+    let args = expand_profiles(args)?;
+
+    // Separa os argumentos posicionais das opções
+    let mut positional: Vec<String> = Vec::new();
+    let mut rootfs = RootfsOptions::default();
+    let mut rootfs_cache_dir: Option<String> = None;
+    let mut security_mirror: Option<String> = None;
+    let mut apt_config = AptConfig::default();
+    let mut ignore_kernel_config = false;
+    let mut extra_kernels: Vec<String> = Vec::new();
+    let mut board_name: Option<String> = None;
+    let mut boards_files: Vec<String> = Vec::new();
+    let mut soc: Option<&'static SocProfile> = None;
+    let mut boot_format: Option<BootFormat> = None;
+    let mut boot = BootOptions::default();
+    let mut bootloader = BootloaderFiles::default();
+
+    let mut index = 1;
+    while index < args.len() {
+        let arg = &args[index];
+
+        if arg == "--keep-apt-proxy" {
+            apt_config.keep_proxy = true;
+            index += 1;
+        } else if arg == "--ignore-kernel-config" {
+            ignore_kernel_config = true;
+            index += 1;
+        } else if arg == "--merge-overlays" {
+            boot.merge_overlays = true;
+            index += 1;
+        } else if arg.starts_with("--") {
+            let value = option_value(&args, index)?;
+            match arg.as_str() {
+                "--rootfs-cache" => rootfs_cache_dir = Some(value.to_string()),
+                "--security-mirror" => security_mirror = Some(value.to_string()),
+                "--apt-conf" => apt_config.conf_snippets.push(value.to_string()),
+                "--apt-repository" => apt_config.repositories.push(value.to_string()),
+                "--apt-keyring" => apt_config.keyrings.push(value.to_string()),
+                "--extra-kernel" => extra_kernels.push(value.to_string()),
+                "--idbloader" => bootloader.idbloader = Some(value.to_string()),
+                "--uboot-itb" => bootloader.uboot_itb = Some(value.to_string()),
+                "--uboot-rockchip" => bootloader.combined = Some(value.to_string()),
+                "--board" => board_name = Some(value.to_string()),
+                "--boards-file" => boards_files.push(value.to_string()),
+                "--soc" => soc = Some(find_soc_profile(value)?),
+                "--boot-format" => boot_format = Some(BootFormat::from_name(value)?),
+                _ if BOOT_OPTIONS.contains(&arg.as_str()) => parse_boot_option(&mut boot, arg, value)?,
+                _ => parse_rootfs_option(&mut rootfs, arg, value)?,
+            }
+            index += 2;
+        } else {
+            positional.push(arg.clone());
+            index += 1;
+        }
+    }
+
+    // O aparelho define o SoC e o DTB, salvo indicação em contrário
+    let board = match board_name {
+        Some(name) => Some(find_board(&name, &boards_files)?),
+        None => None,
+    };
+    let soc = soc.or(board.as_ref().map(|board| board.soc));
+    let dtb_file = positional_dtb_file(&positional, None, board.as_ref(), soc)?;
+
+    if let Some(board) = &board {
+        rootfs.packages.added.extend(board.packages());
+    }
+
+    // O novo sistema precisa do dtc apenas para gerar imagens FIT e aplicar
+    // overlays nos kernels instalados depois
+    let boot_format = boot_format.or(soc.map(|soc| soc.boot_format)).unwrap_or(BootFormat::Legacy);
+
+    rootfs.packages.added.extend(
+        boot_format.dependencies()
+            .iter()
+            .chain(overlay_dependencies(&boot.overlays, boot.merge_overlays))
+            .map(|package| package.to_string())
+    );
+
+    Ok(InstallOptions {
+        storage_device_path: positional[0].clone(),
+        kernel: positional[1].clone(),
+        dtb_file,
+        rootfs,
+        rootfs_cache_dir,
+        security_mirror,
+        apt_config,
+        ignore_kernel_config,
+        extra_kernels,
+        board,
+        soc,
+        boot_format,
+        boot,
+        bootloader,
+    })
+}
+
+pub fn parse_build_rootfs_options(
+    args: &[String]
+) -> Result<(String, RootfsOptions), std::io::Error> {
+    // Obtém o arquivo de saída e as opções do subcomando build-rootfs
+    let rootfs = RootfsOptions {
+        backend: RootfsBackend::Mmdebstrap,
+        include_packages: true,
+        ..RootfsOptions::default()
+    };
+
+    parse_subcommand_options(args, rootfs, "O arquivo de saída não foi informado!")
+}
+
+pub fn parse_prepare_offline_repo_options(
+    args: &[String]
+) -> Result<(String, RootfsOptions), std::io::Error> {
+    // Obtém o diretório e as opções do subcomando prepare-offline-repo
+    parse_subcommand_options(args, RootfsOptions::default(), "O diretório do repositório não foi informado!")
+}
+
+pub fn parse_update_kernel_options(
+    args: &[String]
+) -> Result<UpdateKernelOptions, std::io::Error> {
+    let args = expand_profiles(args)?;
+
+    // Obtém os argumentos e opções do subcomando update-kernel
+    let mut positional: Vec<String> = Vec::new();
+    let mut apt_proxy: Option<String> = None;
+    let mut ignore_kernel_config = false;
+    let mut board_name: Option<String> = None;
+    let mut boards_files: Vec<String> = Vec::new();
+    let mut soc: Option<&'static SocProfile> = None;
+    let mut boot_format: Option<BootFormat> = None;
+    let mut boot = BootOptions::default();
+    let mut dtb_file: Option<String> = None;
+
+    let mut index = 2;
+    while index < args.len() {
+        let arg = &args[index];
+
+        if arg == "--ignore-kernel-config" {
+            ignore_kernel_config = true;
+            index += 1;
+        } else if arg == "--merge-overlays" {
+            boot.merge_overlays = true;
+            index += 1;
+        } else if arg.starts_with("--") {
+            let value = option_value(&args, index)?;
+            match arg.as_str() {
+                "--apt-proxy" => apt_proxy = Some(value.to_string()),
+                "--dtb" => dtb_file = Some(value.to_string()),
+                "--board" => board_name = Some(value.to_string()),
+                "--boards-file" => boards_files.push(value.to_string()),
+                "--soc" => soc = Some(find_soc_profile(value)?),
+                "--boot-format" => boot_format = Some(BootFormat::from_name(value)?),
+                _ if BOOT_OPTIONS.contains(&arg.as_str()) => parse_boot_option(&mut boot, arg, value)?,
+                _ => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::Other,
+                        format!("Opção desconhecida: {}!", arg)
+                    ));
+                }
+            }
+            index += 2;
+        } else {
+            positional.push(arg.clone());
+            index += 1;
+        }
+    }
+
+    // O aparelho define o SoC e o DTB, salvo indicação em contrário
+    let board = match board_name {
+        Some(name) => Some(find_board(&name, &boards_files)?),
+        None => None,
+    };
+    let soc = soc.or(board.as_ref().map(|board| board.soc));
+    let dtb_file = positional_dtb_file(&positional, dtb_file.as_deref(), board.as_ref(), soc)?;
+
+    Ok(UpdateKernelOptions {
+        target: positional[0].clone(),
+        kernel: positional[1].clone(),
+        dtb_file,
+        apt_proxy,
+        ignore_kernel_config,
+        board,
+        soc,
+        boot_format: boot_format.or(soc.map(|soc| soc.boot_format)).unwrap_or(BootFormat::Legacy),
+        boot,
+    })
+}
+
+fn positional_dtb_file(
+    positional: &[String],
+    dtb_file: Option<&str>,
+    board: Option<&Board>,
+    soc: Option<&SocProfile>
+) -> Result<String, std::io::Error> {
+    // O DTB pode ser omitido quando informado com --dtb ou quando o aparelho
+    // ou o SoC é informado. Sem eles, o DTB é detectado pelo device tree do
+    // aparelho em execução
+    match (positional.len(), dtb_file, board, soc) {
+        (3, _, _, _) => Ok(positional[2].clone()),
+        (2, Some(dtb_file), _, _) => Ok(dtb_file.to_string()),
+        (2, None, Some(board), _) => Ok(board.dtb_file.clone()),
+        (2, None, None, Some(soc)) => Ok(soc.default_dtb.to_string()),
+        (2, None, None, None) => Ok(AUTO_DTB_FILE.to_string()),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Número de argumentos incorreto!"
+        )),
+    }
+}
+
+fn parse_subcommand_options(
+    args: &[String],
+    mut rootfs: RootfsOptions,
+    missing_target_message: &str
+) -> Result<(String, RootfsOptions), std::io::Error> {
+    let args = expand_profiles(args)?;
+
+    // Obtém o destino e as opções de um subcomando
+    let mut target: Option<String> = None;
+
+    let mut index = 2;
+    while index < args.len() {
+        let arg = &args[index];
+
+        if arg.starts_with("--") {
+            let value = option_value(&args, index)?;
+            parse_rootfs_option(&mut rootfs, arg, value)?;
+            index += 2;
+        } else if target.is_none() {
+            target = Some(arg.clone());
+            index += 1;
+        } else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Argumento inesperado: {}!", arg)
+            ));
+        }
+    }
+
+    match target {
+        Some(target) => Ok((target, rootfs)),
+        None => Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            missing_target_message
+        )),
+    }
+}
+
+fn expand_profiles(args: &[String]) -> Result<Vec<String>, std::io::Error> {
+    // Substitui cada --profile <arquivo> pelas opções contidas no perfil, de
+    // modo que opções informadas depois dele na linha de comando prevaleçam
+    let mut expanded: Vec<String> = Vec::new();
+
+    let mut index = 0;
+    while index < args.len() {
+        if args[index] != "--profile" {
+            expanded.push(args[index].clone());
+            index += 1;
+            continue;
+        }
+
+        let filepath = option_value(args, index)?;
+
+        for (key, value) in load_profile(filepath)? {
+            let option = format!("--{}", key);
+
+            if FLAG_OPTIONS.contains(&option.as_str()) {
+                match value.as_str() {
+                    "true" | "yes" | "sim" => expanded.push(option),
+                    "false" | "no" | "não" => {},
+                    _ => {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::Other,
+                            format!("Valor inválido para {} no perfil {}: {}!", key, filepath, value)
+                        ));
+                    }
+                }
+            } else {
+                expanded.push(option);
+                expanded.push(value);
+            }
+        }
+
+        index += 2;
+    }
+
+    Ok(expanded)
+}
+
+fn parse_number(
+    option: &str,
+    value: &str
+) -> Result<u32, std::io::Error> {
+    match value.parse::<u32>() {
+        Ok(number) => Ok(number),
+        Err(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Valor inválido para {}: {}!", option, value)
+        )),
+    }
+}
+
+fn option_value(
+    args: &[String],
+    index: usize
+) -> Result<&str, std::io::Error> {
+    // Obtém o valor que acompanha uma opção
+    match args.get(index + 1) {
+        Some(value) => Ok(value.as_str()),
+        None => Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("A opção {} precisa de um valor!", args[index])
+        )),
+    }
+}
+
+fn parse_boot_option(
+    boot: &mut BootOptions,
+    arg: &str,
+    value: &str
+) -> Result<(), std::io::Error> {
+    // Os parâmetros vão entre aspas no setenv bootargs dos scripts do U-Boot
+    if (arg == "--console" || arg == "--kernel-args") && value.contains(['"', '$', '\\']) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("O valor de {} não pode conter aspas, $ ou \\: {}!", arg, value)
+        ));
+    }
+
+    match arg {
+        "--overlay" => boot.overlays.push(value.to_string()),
+        "--console" => boot.command_line.console = Some(value.to_string()),
+        "--loglevel" => boot.command_line.loglevel = Some(parse_number(arg, value)?),
+        "--kernel-args" => boot.command_line.extra_args.extend(value.split_whitespace().map(|arg| arg.to_string())),
+        "--remove-kernel-args" => boot.command_line.removed_args.extend(split_list(value)),
+        "--boot-scripts" => {
+            // none desativa os scripts definidos pelo SoC
+            let scripts: Vec<String> = split_list(value).into_iter().filter(|script| script != "none").collect();
+
+            for script in &scripts {
+                check_boot_script_name(script)?;
+            }
+
+            boot.scripts = Some(scripts);
+        },
+        "--boot-timeout" => {
+            // O extlinux.conf usa décimos de segundo
+            let timeout = parse_number(arg, value)?;
+
+            if timeout > MAX_BOOT_TIMEOUT {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("O valor máximo de --boot-timeout é {}!", MAX_BOOT_TIMEOUT)
+                ));
+            }
+
+            boot.timeout = timeout;
+        },
+        _ => {},
+    }
+
+    Ok(())
+}
+
+fn parse_rootfs_option(
+    rootfs: &mut RootfsOptions,
+    option: &str,
+    value: &str
+) -> Result<(), std::io::Error> {
+    // Aplica uma opção relacionada à criação da raiz
+    match option {
+        "--rootfs-backend" => {
+            rootfs.backend = RootfsBackend::from_name(value)?;
+        },
+        "--suite" => {
+            rootfs.suite = value.to_string();
+        },
+        "--architecture" => {
+            rootfs.architecture = Some(value.to_string());
+        },
+        "--mirror" => {
+            rootfs.mirrors.push(value.to_string());
+        },
+        "--hook" => {
+            rootfs.hooks.push(RootfsHook::parse(value)?);
+        },
+        "--package-set" => {
+            rootfs.packages.set_package_set(value)?;
+        },
+        "--add-packages" => {
+            rootfs.packages.added.extend(split_list(value));
+        },
+        "--remove-packages" => {
+            rootfs.packages.remove_packages(split_list(value))?;
+        },
+        "--enable-units" => {
+            rootfs.packages.enabled_units.extend(split_list(value));
+        },
+        "--disable-units" => {
+            rootfs.packages.disabled_units.extend(split_list(value));
+        },
+        "--mask-units" => {
+            rootfs.packages.masked_units.extend(split_list(value));
+        },
+        "--apt-proxy" => {
+            rootfs.proxy = Some(value.to_string());
+        },
+        "--offline-repo" => {
+            // O caminho precisa ser absoluto para ser usado em URIs file:
+            match fs::canonicalize(value) {
+                Ok(path) => rootfs.offline_repo = Some(path.to_string_lossy().to_string()),
+                Err(_) => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::Other,
+                        format!("O repositório local {} não foi encontrado!", value)
+                    ));
+                }
+            }
+        },
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Opção desconhecida: {}!", option)
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn parse_list_boards_options(
+    args: &[String]
+) -> Result<Vec<String>, std::io::Error> {
+    // Obtém os arquivos de aparelhos adicionais do subcomando list-boards
+    let mut boards_files: Vec<String> = Vec::new();
+
+    let mut index = 2;
+    while index < args.len() {
+        let arg = &args[index];
+
+        match arg.as_str() {
+            "--boards-file" => boards_files.push(option_value(args, index)?.to_string()),
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("Opção desconhecida: {}!", arg)
+                ));
+            }
+        }
+        index += 2;
+    }
+
+    Ok(boards_files)
+}
+
+pub fn parse_test_boot_options(
+    args: &[String]
+) -> Result<BootTestOptions, std::io::Error> {
+    // Obtém os argumentos e opções do subcomando test-boot
+    let mut positional: Vec<String> = Vec::new();
+    let mut options = BootTestOptions {
+        kernel: String::new(),
+        disk: String::new(),
+        initrd: None,
+        architecture: None,
+        root: None,
+        extra_args: Vec::new(),
+        marker: DEFAULT_TEST_BOOT_MARKER.to_string(),
+        timeout: DEFAULT_TEST_BOOT_TIMEOUT,
+        memory: DEFAULT_TEST_BOOT_MEMORY,
+        log: None,
+    };
+
+    let mut index = 2;
+    while index < args.len() {
+        let arg = &args[index];
+
+        if arg.starts_with("--") {
+            let value = option_value(args, index)?;
+            match arg.as_str() {
+                "--initrd" => options.initrd = Some(value.to_string()),
+                "--architecture" => options.architecture = Some(KernelArchitecture::from_name(value)?),
+                "--root" => options.root = Some(value.to_string()),
+                "--kernel-args" => options.extra_args.extend(value.split_whitespace().map(|arg| arg.to_string())),
+                "--marker" => options.marker = value.to_string(),
+                "--timeout" => options.timeout = parse_number(arg, value)?,
+                "--memory" => options.memory = parse_number(arg, value)?,
+                "--log" => options.log = Some(value.to_string()),
+                _ => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::Other,
+                        format!("Opção desconhecida: {}!", arg)
+                    ));
+                }
+            }
+            index += 2;
+        } else {
+            positional.push(arg.clone());
+            index += 1;
+        }
+    }
+
+    if positional.len() != 2 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Número de argumentos incorreto!"
+        ));
+    }
+
+    options.kernel = positional[0].clone();
+    options.disk = positional[1].clone();
+
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_boot_timeout_up_to_limit() {
+        let mut boot = BootOptions::default();
+
+        parse_boot_option(&mut boot, "--boot-timeout", "0").unwrap();
+        assert_eq!(boot.timeout, 0);
+
+        parse_boot_option(&mut boot, "--boot-timeout", &MAX_BOOT_TIMEOUT.to_string()).unwrap();
+        assert_eq!(boot.timeout, MAX_BOOT_TIMEOUT);
+    }
+
+    #[test]
+    fn rejects_boot_timeout_above_limit() {
+        let mut boot = BootOptions::default();
+
+        assert!(parse_boot_option(&mut boot, "--boot-timeout", &(MAX_BOOT_TIMEOUT + 1).to_string()).is_err());
+        assert!(parse_boot_option(&mut boot, "--boot-timeout", "4294967295").is_err());
+        assert!(parse_boot_option(&mut boot, "--boot-timeout", "-1").is_err());
+        assert_eq!(boot.timeout, DEFAULT_BOOT_TIMEOUT);
+    }
+
+    #[test]
+    fn rejects_quotes_in_kernel_args() {
+        let mut boot = BootOptions::default();
+
+        assert!(parse_boot_option(&mut boot, "--kernel-args", "quiet \"splash\"").is_err());
+        assert!(parse_boot_option(&mut boot, "--console", "${console}").is_err());
+        assert!(boot.command_line.extra_args.is_empty());
+        assert!(boot.command_line.console.is_none());
+    }
+    fn update_kernel_args(extra: &[&str]) -> Vec<String> {
+        ["installer", "update-kernel"]
+            .iter()
+            .chain(extra.iter())
+            .map(|arg| arg.to_string())
+            .collect()
+    }
+
+    fn saved_boot_profile(name: &str) -> String {
+        let soc = find_soc_profile("rk3328").unwrap();
+        let mut boot = BootOptions::default();
+        boot.command_line.console = Some("ttyS2,1500000n8".to_string());
+        boot.command_line.extra_args = vec!["cma=256M".to_string()];
+        boot.overlays = vec!["rk3328-uart1".to_string()];
+        boot.scripts = Some(Vec::new());
+        boot.timeout = 10;
+
+        let filepath = std::env::temp_dir().join(format!("installer-{}-{}.profile", name, std::process::id()));
+        fs::write(&filepath, render_boot_profile(None, Some(soc), "rockchip/rk3328-rock64.dtb", BootFormat::Legacy, &boot, &boot.overlays)).unwrap();
+        filepath.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn update_kernel_uses_saved_boot_profile() {
+        let profile = saved_boot_profile("saved-boot");
+        let options = parse_update_kernel_options(&update_kernel_args(&["--profile", &profile, "/", "linux-image-arm64"])).unwrap();
+
+        assert_eq!(options.soc.unwrap().name, "rk3328");
+        assert_eq!(options.dtb_file, "rockchip/rk3328-rock64.dtb");
+        assert!(options.boot_format == BootFormat::Legacy);
+        assert_eq!(options.boot.command_line.console.as_deref(), Some("ttyS2,1500000n8"));
+        assert_eq!(options.boot.command_line.extra_args, vec!["cma=256M"]);
+        assert_eq!(options.boot.overlays, vec!["rk3328-uart1"]);
+        assert_eq!(options.boot.scripts, Some(Vec::new()));
+        assert_eq!(options.boot.timeout, 10);
+
+        fs::remove_file(profile).unwrap();
+    }
+
+    #[test]
+    fn command_line_overrides_saved_boot_profile() {
+        let profile = saved_boot_profile("override-boot");
+        let options = parse_update_kernel_options(&update_kernel_args(&[
+            "--profile", &profile,
+            "/", "linux-image-arm64", "rockchip/rk3328-roc-cc.dtb",
+            "--console", "ttyS1,115200n8",
+            "--boot-format", "fit",
+        ])).unwrap();
+
+        assert_eq!(options.dtb_file, "rockchip/rk3328-roc-cc.dtb");
+        assert!(options.boot_format == BootFormat::Fit);
+        assert_eq!(options.boot.command_line.console.as_deref(), Some("ttyS1,115200n8"));
+        assert_eq!(options.boot.timeout, 10);
+
+        fs::remove_file(profile).unwrap();
+    }
+}
\ No newline at end of file
@@ -0,0 +1,135 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::configure_boot::*;
+use crate::constants::*;
+use crate::fdt::*;
+use crate::kernel::*;
+
+pub fn overlay_file_name(overlay: &str) -> String {
+    // Nome do overlay em /boot/dtb/overlays
+    let filename = overlay.rsplit('/').next().unwrap_or(overlay);
+
+    if filename.ends_with(".dtbo") {
+        filename.to_string()
+    } else {
+        format!("{}.dtbo", filename)
+    }
+}
+
+pub fn overlay_dependencies(
+    overlays: &[String],
+    merge_overlays: bool
+) -> &'static [&'static str] {
+    // O fdtoverlay faz parte do device-tree-compiler
+    if !overlays.is_empty() && merge_overlays {
+        &["device-tree-compiler"]
+    } else {
+        &[]
+    }
+}
+
+pub fn resolve_overlay_files(
+    kernel: &KernelFiles,
+    overlays: &[String]
+) -> Result<Vec<PathBuf>, std::io::Error> {
+    // Um overlay pode ser um arquivo .dtbo ou o nome de um overlay
+    // distribuído junto com os DTBs do kernel
+    let mut overlay_files: Vec<PathBuf> = Vec::new();
+
+    for overlay in overlays {
+        let overlay_file = if Path::new(overlay).is_file() {
+            PathBuf::from(overlay)
+        } else {
+            match find_overlay(Path::new(&kernel.dtb_dir), &overlay_file_name(overlay)) {
+                Some(overlay_file) => overlay_file,
+                None => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::Other,
+                        format!("O overlay {} não foi encontrado no kernel {}!", overlay, kernel.release)
+                    ));
+                }
+            }
+        };
+
+        read_device_tree(&overlay_file.to_string_lossy())?;
+        overlay_files.push(overlay_file);
+    }
+
+    Ok(overlay_files)
+}
+
+fn find_overlay(
+    dir: &Path,
+    filename: &str
+) -> Option<PathBuf> {
+    // Procura o overlay nos subdiretórios dos DTBs (ex.: rockchip/overlay/)
+    for entry in fs::read_dir(dir).ok()?.flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            if let Some(found) = find_overlay(&path, filename) {
+                return Some(found);
+            }
+        } else if entry.file_name().to_string_lossy() == filename {
+            return Some(path);
+        }
+    }
+
+    None
+}
+
+pub fn install_overlays(
+    kernel: &KernelFiles,
+    boot: &BootConfig
+) -> Result<(), std::io::Error> {
+    let kernel_release = kernel.release.as_str();
+    let overlay_files = resolve_overlay_files(kernel, &boot.overlays)?;
+
+    // Copia os overlays para /boot/dtb-<kernel_release>/overlays
+    let overlays_dir = format!("{}/boot/dtb-{}/overlays", ROOT_MOUNT_POINT, kernel_release);
+
+    if let Err(_) = fs::create_dir_all(overlays_dir.as_str()) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao criar o diretório /boot/dtb-{}/overlays!", kernel_release)
+        ));
+    }
+
+    for (overlay, overlay_file) in boot.overlays.iter().zip(&overlay_files) {
+        let destination_file = format!("{}/{}", overlays_dir, overlay_file_name(overlay));
+
+        if let Err(_) = fs::copy(overlay_file, destination_file) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao copiar o overlay {}!", overlay)
+            ));
+        }
+    }
+
+    if !boot.merges_overlays() {
+        return Ok(());
+    }
+
+    // Aplica os overlays sobre o DTB do aparelho, para versões do U-Boot sem
+    // suporte a FDTOVERLAYS e para as imagens FIT
+    let dtb_path = format!("{}/boot/dtb-{}/{}", ROOT_MOUNT_POINT, kernel_release, boot.dtb_file);
+
+    let output = Command::new("fdtoverlay")
+        .arg("--input")
+        .arg(dtb_path.as_str())
+        .arg("--output")
+        .arg(dtb_path.as_str())
+        .args(boot.overlays.iter().map(|overlay| format!("{}/{}", overlays_dir, overlay_file_name(overlay))))
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao aplicar os overlays ao DTB {}! {}", boot.dtb_file, String::from_utf8_lossy(&output.stderr).trim())
+        ));
+    }
+
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,181 @@
+pub const PACKAGE_SET_NAMES: &[&str] = &["minimal", "server", "desktop-lite", "kiosk"];
+
+const MINIMAL_PACKAGES: &[&str] = &[
+    "u-boot-tools",
+    "initramfs-tools",
+    "udev",
+    "sudo",
+    "iputils-ping",
+    "network-manager",
+];
+
+// Pacotes dos quais o boot do novo sistema depende, que não podem ser removidos
+const BOOT_CHAIN_PACKAGES: &[&str] = &[
+    "u-boot-tools",
+    "device-tree-compiler",
+    "initramfs-tools",
+    "udev",
+    "kmod",
+    "e2fsprogs",
+    "systemd",
+    "systemd-sysv",
+    "init",
+];
+
+const SERVER_PACKAGES: &[&str] = &[
+    "wget",
+    "curl",
+    "ntpdate",
+    "openssh-server",
+    "dosfstools",
+    "tar",
+    "zip",
+    "unzip",
+];
+
+const DESKTOP_LITE_PACKAGES: &[&str] = &[
+    "xserver-xorg",
+    "xfce4",
+    "xfce4-terminal",
+    "lightdm",
+    "network-manager-gnome",
+    "firefox-esr",
+];
+
+const KIOSK_PACKAGES: &[&str] = &[
+    "xserver-xorg",
+    "xinit",
+    "openbox",
+    "chromium",
+    "unclutter",
+];
+
+pub struct PackageSelection {
+    pub set: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub enabled_units: Vec<String>,
+    pub disabled_units: Vec<String>,
+    pub masked_units: Vec<String>,
+}
+
+impl Default for PackageSelection {
+    fn default() -> Self {
+        PackageSelection {
+            set: "server".to_string(),
+            added: Vec::new(),
+            removed: Vec::new(),
+            enabled_units: Vec::new(),
+            disabled_units: Vec::new(),
+            masked_units: Vec::new(),
+        }
+    }
+}
+
+impl PackageSelection {
+    pub fn set_package_set(&mut self, name: &str) -> Result<(), std::io::Error> {
+        if !PACKAGE_SET_NAMES.contains(&name) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!(
+                    "Conjunto de pacotes desconhecido: {}! Use {}.",
+                    name,
+                    PACKAGE_SET_NAMES.join(", ")
+                )
+            ));
+        }
+
+        self.set = name.to_string();
+
+        Ok(())
+    }
+
+    pub fn remove_packages(&mut self, packages: Vec<String>) -> Result<(), std::io::Error> {
+        if let Some(package) = packages.iter().find(|package| BOOT_CHAIN_PACKAGES.contains(&package.as_str())) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!(
+                    "O pacote {} não pode ser removido, pois o boot do novo sistema depende dele! Pacotes protegidos: {}.",
+                    package,
+                    BOOT_CHAIN_PACKAGES.join(", ")
+                )
+            ));
+        }
+
+        self.removed.extend(packages);
+
+        Ok(())
+    }
+
+    pub fn packages(&self) -> Vec<String> {
+        // Junta os pacotes do conjunto com os adicionados pelo usuário,
+        // descartando os removidos
+        let mut set_packages: Vec<&str> = MINIMAL_PACKAGES.to_vec();
+
+        match self.set.as_str() {
+            "server" => set_packages.extend_from_slice(SERVER_PACKAGES),
+            "desktop-lite" => {
+                set_packages.extend_from_slice(SERVER_PACKAGES);
+                set_packages.extend_from_slice(DESKTOP_LITE_PACKAGES);
+            },
+            "kiosk" => set_packages.extend_from_slice(KIOSK_PACKAGES),
+            _ => {}
+        }
+
+        let mut packages: Vec<String> = Vec::new();
+
+        for package in set_packages.iter().map(|package| package.to_string()).chain(self.added.iter().cloned()) {
+            if !self.removed.contains(&package) && !packages.contains(&package) {
+                packages.push(package);
+            }
+        }
+
+        packages
+    }
+
+    pub fn units_to_enable(&self) -> Vec<String> {
+        // Unidades habilitadas pelo conjunto e pelo usuário, exceto as
+        // desabilitadas ou mascaradas explicitamente
+        let set_units: &[&str] = match self.set.as_str() {
+            "server" => &["NetworkManager", "ssh"],
+            "desktop-lite" => &["NetworkManager", "ssh", "lightdm"],
+            _ => &["NetworkManager"],
+        };
+
+        let mut units: Vec<String> = Vec::new();
+
+        for unit in set_units.iter().map(|unit| unit.to_string()).chain(self.enabled_units.iter().cloned()) {
+            if !self.disabled_units.contains(&unit)
+                && !self.masked_units.contains(&unit)
+                && !units.contains(&unit) {
+                units.push(unit);
+            }
+        }
+
+        units
+    }
+
+    pub fn describe(&self) -> String {
+        // Descreve a seleção para compor a chave do cache da raiz
+        let mut packages = self.packages();
+        packages.sort();
+
+        format!(
+            "packages {}\nremove {}\nenable {}\ndisable {}\nmask {}\n",
+            packages.join(" "),
+            self.removed.join(" "),
+            self.units_to_enable().join(" "),
+            self.disabled_units.join(" "),
+            self.masked_units.join(" ")
+        )
+    }
+}
+
+pub fn split_list(value: &str) -> Vec<String> {
+    // Separa listas de pacotes ou unidades por espaços ou vírgulas
+    value
+        .split(|character: char| character.is_whitespace() || character == ',')
+        .filter(|item| !item.is_empty())
+        .map(|item| item.to_string())
+        .collect()
+}
\ No newline at end of file
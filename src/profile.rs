@@ -0,0 +1,258 @@
+use std::fs;
+use std::path::Path;
+
+use crate::boards::*;
+use crate::configure_boot::*;
+use crate::constants::*;
+use crate::overlays::*;
+use crate::soc_profiles::*;
+
+pub fn load_profile(
+    filepath: &str
+) -> Result<Vec<(String, String)>, std::io::Error> {
+    // Lê o perfil de instalação
+    let contents = match fs::read_to_string(filepath) {
+        Ok(contents) => contents,
+        Err(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao ler o perfil {}!", filepath)
+            ));
+        }
+    };
+
+    // Interpreta as linhas no formato <chave> = <valor>, ignorando linhas
+    // em branco e comentários
+    let mut entries: Vec<(String, String)> = Vec::new();
+
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match line.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                entries.push((key.trim().to_string(), value.trim().to_string()));
+            },
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("Linha {} inválida no perfil {}!", number + 1, filepath)
+                ));
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+pub fn render_boot_profile(
+    board: Option<&Board>,
+    soc: Option<&SocProfile>,
+    dtb_file: &str,
+    boot_format: BootFormat,
+    boot: &BootOptions,
+    overlays: &[String]
+) -> String {
+    // Gera o perfil com as configurações de boot da instalação, carregado
+    // pelo update-kernel antes das opções da linha de comando. O SoC, o DTB
+    // e o formato só são gravados quando diferem dos definidos pelo aparelho
+    // ou pelo SoC, para que um --board ou --soc informado depois prevaleça
+    let mut profile = String::from("# Configurações de boot usadas na instalação, carregadas pelo update-kernel\n");
+
+    if let Some(board) = board {
+        profile += format!("board = {}\n", board.name).as_str();
+    }
+
+    if let Some(soc) = soc {
+        if board.map(|board| board.soc.name) != Some(soc.name) {
+            profile += format!("soc = {}\n", soc.name).as_str();
+        }
+    }
+
+    let default_dtb_file = match (board, soc) {
+        (Some(board), _) => Some(board.dtb_file.as_str()),
+        (None, Some(soc)) => Some(soc.default_dtb),
+        (None, None) => None,
+    };
+
+    if default_dtb_file != Some(dtb_file) {
+        profile += format!("dtb = {}\n", dtb_file).as_str();
+    }
+
+    if soc.map(|soc| soc.boot_format).unwrap_or(BootFormat::Legacy) != boot_format {
+        profile += format!("boot-format = {}\n", boot_format.name()).as_str();
+    }
+
+    if let Some(console) = &boot.command_line.console {
+        profile += format!("console = {}\n", console).as_str();
+    }
+
+    if let Some(loglevel) = boot.command_line.loglevel {
+        profile += format!("loglevel = {}\n", loglevel).as_str();
+    }
+
+    if !boot.command_line.extra_args.is_empty() {
+        profile += format!("kernel-args = {}\n", boot.command_line.extra_args.join(" ")).as_str();
+    }
+
+    if !boot.command_line.removed_args.is_empty() {
+        profile += format!("remove-kernel-args = {}\n", boot.command_line.removed_args.join(",")).as_str();
+    }
+
+    for overlay in overlays {
+        profile += format!("overlay = {}\n", overlay).as_str();
+    }
+
+    profile += format!("merge-overlays = {}\n", boot.merge_overlays).as_str();
+
+    if let Some(scripts) = &boot.scripts {
+        let scripts = if scripts.is_empty() { "none".to_string() } else { scripts.join(",") };
+        profile += format!("boot-scripts = {}\n", scripts).as_str();
+    }
+
+    profile += format!("boot-timeout = {}\n", boot.timeout).as_str();
+
+    profile
+}
+
+pub fn save_boot_overlays(
+    target_root: &str,
+    overlays: &[String]
+) -> Result<Vec<String>, std::io::Error> {
+    // Os overlays informados como arquivo são copiados para o sistema
+    // instalado, onde o update-kernel os encontra; os do kernel ficam pelo nome
+    let mut saved_overlays: Vec<String> = Vec::new();
+
+    for overlay in overlays {
+        if !Path::new(overlay).is_file() {
+            saved_overlays.push(overlay.clone());
+            continue;
+        }
+
+        let saved_overlay = format!("{}/{}", SAVED_OVERLAYS_DIR, overlay_file_name(overlay));
+        let destination = format!("{}{}", target_root, saved_overlay);
+
+        // Na atualização o overlay salvo já está no lugar
+        if *overlay != destination {
+            if let Err(_) = fs::create_dir_all(format!("{}{}", target_root, SAVED_OVERLAYS_DIR)) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("Falha ao criar o diretório {}!", SAVED_OVERLAYS_DIR)
+                ));
+            }
+
+            if let Err(_) = fs::copy(overlay, &destination) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("Falha ao copiar o overlay {} para {}!", overlay, SAVED_OVERLAYS_DIR)
+                ));
+            }
+        }
+
+        saved_overlays.push(saved_overlay);
+    }
+
+    Ok(saved_overlays)
+}
+
+pub fn saved_overlay_path(
+    target_root: &str,
+    overlay: &str
+) -> String {
+    // Os overlays salvos ficam na raiz montada durante o update-kernel
+    if overlay.starts_with(SAVED_OVERLAYS_DIR) {
+        format!("{}{}", target_root, overlay)
+    } else {
+        overlay.to_string()
+    }
+}
+
+pub fn set_boot_profile(
+    target_root: &str,
+    contents: &str
+) -> Result<(), std::io::Error> {
+    // Cria o arquivo /etc/installer/boot.profile
+    let filepath = Path::new(target_root).join(BOOT_PROFILE.trim_start_matches('/'));
+
+    if let Some(parent) = filepath.parent() {
+        if let Err(_) = fs::create_dir_all(parent) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao criar o diretório de {}!", BOOT_PROFILE)
+            ));
+        }
+    }
+
+    if let Err(_) = fs::write(filepath, contents) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao criar o arquivo {}!", BOOT_PROFILE)
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::golden::*;
+
+    fn boot_options() -> BootOptions {
+        let mut boot = BootOptions::default();
+        boot.command_line.console = Some("ttyS2,115200n8".to_string());
+        boot.command_line.loglevel = Some(4);
+        boot.command_line.extra_args = vec!["cma=256M".to_string(), "quiet".to_string()];
+        boot.command_line.removed_args = vec!["earlycon".to_string(), "earlyprintk".to_string()];
+        boot.overlays = vec!["rk3328-uart1".to_string(), "custom.dtbo".to_string()];
+        boot.scripts = Some(Vec::new());
+        boot.timeout = 10;
+        boot
+    }
+
+    #[test]
+    fn renders_boot_profile_for_board() {
+        let board = find_board("h96-mini", &[]).unwrap();
+
+        assert_golden(
+            "boot.profile-board",
+            &render_boot_profile(Some(&board), Some(board.soc), &board.dtb_file, board.soc.boot_format, &BootOptions::default(), &[])
+        );
+    }
+
+    #[test]
+    fn renders_boot_profile_for_soc_with_custom_dtb() {
+        let soc = find_soc_profile("rk3328").unwrap();
+        let boot = boot_options();
+
+        assert_golden(
+            "boot.profile-soc",
+            &render_boot_profile(None, Some(soc), "rockchip/rk3328-rock64.dtb", BootFormat::Legacy, &boot, &boot.overlays)
+        );
+    }
+
+    #[test]
+    fn saves_overlay_files_into_target_root() {
+        let target_root = std::env::temp_dir().join(format!("installer-overlays-{}", std::process::id()));
+        let target = target_root.to_str().unwrap();
+        let overlay_file = target_root.join("custom.dtbo");
+        fs::create_dir_all(&target_root).unwrap();
+        fs::write(&overlay_file, b"dtbo").unwrap();
+
+        let overlays = vec!["rk3328-uart1".to_string(), overlay_file.to_str().unwrap().to_string()];
+        let saved_overlays = save_boot_overlays(target, &overlays).unwrap();
+
+        assert_eq!(saved_overlays, vec!["rk3328-uart1", "/etc/installer/overlays/custom.dtbo"]);
+        assert_eq!(fs::read(target_root.join("etc/installer/overlays/custom.dtbo")).unwrap(), b"dtbo");
+
+        // Na atualização o overlay salvo é lido da raiz montada e mantido
+        let mounted_overlays: Vec<String> = saved_overlays.iter().map(|overlay| saved_overlay_path(target, overlay)).collect();
+        assert_eq!(save_boot_overlays(target, &mounted_overlays).unwrap(), saved_overlays);
+        assert_eq!(fs::read(target_root.join("etc/installer/overlays/custom.dtbo")).unwrap(), b"dtbo");
+
+        fs::remove_dir_all(&target_root).unwrap();
+    }
+}
\ No newline at end of file
@@ -0,0 +1,195 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crate::apt_config::*;
+use crate::constants::*;
+use crate::install::*;
+
+pub fn rootfs_cache_key(
+    rootfs: &RootfsOptions,
+    apt_config: &AptConfig
+) -> Result<String, std::io::Error> {
+    // Calcula a chave do cache a partir de tudo que influencia o conteúdo da raiz
+    let mut description = String::new();
+
+    description += rootfs.backend.name();
+    description += "\n";
+    description += &rootfs.mirrors().join(" ");
+    description += "\n";
+
+    for hook in &rootfs.hooks {
+        description += format!("{}:{}\n", hook.phase, hook.command).as_str();
+    }
+
+    description += &apt_config.describe(rootfs.proxy.as_deref())?;
+    description += &rootfs.packages.describe();
+
+    Ok(format!(
+        "{}-{}-{:016x}",
+        rootfs.suite,
+        rootfs.architecture(),
+        fnv1a_hash(description.as_bytes())
+    ))
+}
+
+pub fn find_cached_rootfs(
+    cache_dir: &str,
+    key: &str
+) -> Option<String> {
+    // Procura um tarball da raiz já gerado para a chave informada
+    let tarball = cached_rootfs_path(cache_dir, key);
+
+    if Path::new(tarball.as_str()).is_file() {
+        Some(tarball)
+    } else {
+        None
+    }
+}
+
+pub fn extract_cached_rootfs(tarball: &str) -> Result<(), std::io::Error> {
+    // Extrai o tarball da raiz na partição raiz
+    let output = Command::new("tar")
+        .arg("--extract")
+        .arg("--preserve-permissions")
+        .arg("--numeric-owner")
+        .arg("--xattrs")
+        .arg("--file")
+        .arg(tarball)
+        .arg("--directory")
+        .arg(ROOT_MOUNT_POINT)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao extrair a raiz armazenada em {}!", tarball)
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn store_rootfs_cache(
+    cache_dir: &str,
+    key: &str
+) -> Result<(), std::io::Error> {
+    // Cria o diretório do cache
+    if let Err(_) = fs::create_dir_all(cache_dir) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao criar o diretório do cache {}!", cache_dir)
+        ));
+    }
+
+    // Gera o tarball em um arquivo temporário para não deixar um cache
+    // incompleto caso a instalação seja interrompida. As chaves SSH e o
+    // machine-id ficam de fora, pois identificam cada aparelho
+    let tarball = cached_rootfs_path(cache_dir, key);
+    let temporary_tarball = format!("{}.partial", tarball);
+
+    let output = Command::new("tar")
+        .arg("--create")
+        .arg("--preserve-permissions")
+        .arg("--numeric-owner")
+        .arg("--xattrs")
+        .arg("--exclude=./lost+found")
+        .arg("--exclude=./var/cache/apt/archives/*.deb")
+        .arg("--exclude=./etc/ssh/ssh_host_*")
+        .arg("--exclude=./etc/machine-id")
+        .arg("--file")
+        .arg(&temporary_tarball)
+        .arg("--directory")
+        .arg(ROOT_MOUNT_POINT)
+        .arg(".")
+        .output()?;
+
+    if !output.status.success() {
+        let _ = fs::remove_file(&temporary_tarball);
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Falha ao armazenar a raiz no cache!"
+        ));
+    }
+
+    if let Err(_) = fs::rename(&temporary_tarball, &tarball) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao mover o tarball da raiz para {}!", tarball)
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn reset_system_identity() -> Result<(), std::io::Error> {
+    // Cria um /etc/machine-id vazio, para que o systemd gere um novo
+    // identificador no primeiro boot
+    if let Err(_) = fs::write(format!("{}/etc/machine-id", ROOT_MOUNT_POINT), "") {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Falha ao criar o arquivo /etc/machine-id!"
+        ));
+    }
+
+    // Remove a cópia do D-Bus, exceto quando é um link para /etc/machine-id
+    let dbus_machine_id = format!("{}/var/lib/dbus/machine-id", ROOT_MOUNT_POINT);
+
+    if let Ok(metadata) = fs::symlink_metadata(dbus_machine_id.as_str()) {
+        if metadata.is_file() && fs::remove_file(dbus_machine_id.as_str()).is_err() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "Falha ao remover o arquivo /var/lib/dbus/machine-id!"
+            ));
+        }
+    }
+
+    // Remove as chaves de host de caches gerados por versões anteriores,
+    // pois o ssh-keygen -A só cria as chaves que faltam
+    if let Ok(entries) = fs::read_dir(format!("{}/etc/ssh", ROOT_MOUNT_POINT)) {
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with("ssh_host_") && fs::remove_file(entry.path()).is_err() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    "Falha ao remover as chaves de host do SSH armazenadas no cache!"
+                ));
+            }
+        }
+    }
+
+    // Gera novas chaves de host do SSH, quando o servidor está instalado
+    if !Path::new(format!("{}/usr/bin/ssh-keygen", ROOT_MOUNT_POINT).as_str()).exists() {
+        return Ok(());
+    }
+
+    let output = Command::new("chroot")
+        .arg(ROOT_MOUNT_POINT)
+        .arg("/usr/bin/ssh-keygen")
+        .arg("-A")
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Falha ao gerar as chaves de host do SSH do novo sistema!"
+        ));
+    }
+
+    Ok(())
+}
+
+fn cached_rootfs_path(cache_dir: &str, key: &str) -> String {
+    format!("{}/rootfs-{}.tar", cache_dir, key)
+}
+
+fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    // Hash FNV-1a de 64 bits, estável entre execuções e versões do Rust
+    let mut hash: u64 = 0xcbf29ce484222325;
+
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    hash
+}
\ No newline at end of file
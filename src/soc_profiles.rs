@@ -0,0 +1,198 @@
+use crate::configure_boot::*;
+use crate::constants::*;
+use crate::kernel::*;
+
+pub struct SocProfile {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub architecture: KernelArchitecture,
+    pub load_address: u32,
+    pub console: &'static str,
+    pub baud_rate: u32,
+    pub default_dtb: &'static str,
+    pub boot_format: BootFormat,
+    pub extra_bootargs: &'static [&'static str],
+    pub boot_scripts: &'static [&'static str],
+    pub rockchip_bootloader: bool,
+}
+
+// Parâmetros de boot de cada família de SoC suportada
+pub const SOC_PROFILES: &[SocProfile] = &[
+    SocProfile {
+        name: "rk322x",
+        aliases: &["rk3228", "rk3229"],
+        architecture: KernelArchitecture::Arm,
+        load_address: 0x600f0000,
+        console: "ttyS2",
+        baud_rate: 1500000,
+        default_dtb: "rk322x-box.dtb",
+        boot_format: BootFormat::Legacy,
+        extra_bootargs: &["earlycon=uart8250,mmio32,0x11030000"],
+        boot_scripts: &[],
+        rockchip_bootloader: true,
+    },
+    SocProfile {
+        name: "rk3328",
+        aliases: &["rk3318"],
+        architecture: KernelArchitecture::Arm64,
+        load_address: 0x02080000,
+        console: "ttyS2",
+        baud_rate: 1500000,
+        default_dtb: "rockchip/rk3318-a95x-z2.dtb",
+        boot_format: BootFormat::Fit,
+        extra_bootargs: &["earlycon=uart8250,mmio32,0xff130000"],
+        boot_scripts: &["boot.scr"],
+        rockchip_bootloader: true,
+    },
+    SocProfile {
+        name: "rk3399",
+        aliases: &[],
+        architecture: KernelArchitecture::Arm64,
+        load_address: 0x02080000,
+        console: "ttyS2",
+        baud_rate: 1500000,
+        default_dtb: "rockchip/rk3399-rockpro64.dtb",
+        boot_format: BootFormat::Fit,
+        extra_bootargs: &["earlycon=uart8250,mmio32,0xff1a0000"],
+        boot_scripts: &["boot.scr"],
+        rockchip_bootloader: true,
+    },
+    SocProfile {
+        name: "s905x",
+        aliases: &["s905w"],
+        architecture: KernelArchitecture::Arm64,
+        load_address: 0x01080000,
+        console: "ttyAML0",
+        baud_rate: 115200,
+        default_dtb: "amlogic/meson-gxl-s905x-p212.dtb",
+        boot_format: BootFormat::Legacy,
+        extra_bootargs: &["earlycon=meson,0xc81004c0", "no_console_suspend"],
+        boot_scripts: &["boot.scr", "s905_autoscript", "aml_autoscript"],
+        rockchip_bootloader: false,
+    },
+    SocProfile {
+        name: "h6",
+        aliases: &["allwinner-h6"],
+        architecture: KernelArchitecture::Arm64,
+        load_address: 0x40080000,
+        console: "ttyS0",
+        baud_rate: 115200,
+        default_dtb: "allwinner/sun50i-h6-tanix-tx6.dtb",
+        boot_format: BootFormat::Legacy,
+        extra_bootargs: &["earlycon=uart,mmio32,0x05000000"],
+        boot_scripts: &[],
+        rockchip_bootloader: false,
+    },
+    SocProfile {
+        name: "h3",
+        aliases: &["allwinner-h3"],
+        architecture: KernelArchitecture::Arm,
+        load_address: 0x42000000,
+        console: "ttyS0",
+        baud_rate: 115200,
+        default_dtb: "sun8i-h3-beelink-x2.dtb",
+        boot_format: BootFormat::Legacy,
+        extra_bootargs: &["earlycon=uart,mmio32,0x01c28000"],
+        boot_scripts: &[],
+        rockchip_bootloader: false,
+    },
+];
+
+pub fn find_soc_profile(
+    name: &str
+) -> Result<&'static SocProfile, std::io::Error> {
+    // Procura o perfil pelo nome ou por um dos nomes alternativos
+    let name = name.to_lowercase();
+
+    match SOC_PROFILES
+        .iter()
+        .find(|profile| profile.name == name || profile.aliases.contains(&name.as_str()))
+    {
+        Some(profile) => Ok(profile),
+        None => Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!(
+                "SoC desconhecido: {}! SoCs disponíveis: {}.",
+                name,
+                SOC_PROFILES.iter().map(|profile| profile.name).collect::<Vec<&str>>().join(", ")
+            )
+        )),
+    }
+}
+
+pub fn create_boot_config(
+    architecture: KernelArchitecture,
+    soc: Option<&SocProfile>,
+    dtb_file: &str,
+    boot_format: BootFormat,
+    options: &BootOptions
+) -> Result<BootConfig, std::io::Error> {
+    let command_line = &options.command_line;
+
+    if command_line.removed_args.iter().any(|arg| arg == "root") {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "O parâmetro root não pode ser removido da linha de comando do kernel!"
+        ));
+    }
+
+    if command_line.loglevel.is_some_and(|loglevel| loglevel > 7) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "O nível de mensagens do kernel deve estar entre 0 e 7!"
+        ));
+    }
+
+    if let Some(soc) = soc {
+        if soc.architecture != architecture {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("O kernel ({}) não é compatível com o SoC {} ({})!", architecture.name(), soc.name, soc.architecture.name())
+            ));
+        }
+    }
+
+    // Sem um SoC informado, mantém o endereço padrão da arquitetura e a
+    // console definida pelo DTB. A console escolhida substitui a do SoC, e
+    // none a desativa
+    let console = match command_line.console.as_deref() {
+        Some("none") => None,
+        Some(console) => Some(console.to_string()),
+        None => soc.map(|soc| format!("{},{}", soc.console, soc.baud_rate)),
+    };
+
+    Ok(BootConfig {
+        architecture,
+        dtb_file: dtb_file.to_string(),
+        boot_format,
+        load_address: soc.map(|soc| soc.load_address).unwrap_or(architecture.default_load_address()),
+        console,
+        loglevel: command_line.loglevel.unwrap_or(DEFAULT_KERNEL_LOGLEVEL),
+        extra_bootargs: soc
+            .map(|soc| soc.extra_bootargs.iter().map(|arg| arg.to_string()).collect())
+            .unwrap_or_default(),
+        custom_bootargs: command_line.extra_args.clone(),
+        removed_bootargs: command_line.removed_args.clone(),
+        overlays: options.overlays.clone(),
+        merge_overlays: options.merge_overlays,
+        scripts: boot_scripts(soc, boot_format, options),
+        timeout: options.timeout,
+    })
+}
+
+fn boot_scripts(
+    soc: Option<&SocProfile>,
+    boot_format: BootFormat,
+    options: &BootOptions
+) -> Vec<String> {
+    // Sem indicação do usuário ou do SoC, o FIT mantém o boot.scr
+    if let Some(scripts) = &options.scripts {
+        return scripts.clone();
+    }
+
+    match soc {
+        Some(soc) => soc.boot_scripts.iter().map(|script| script.to_string()).collect(),
+        None if boot_format == BootFormat::Fit => vec!["boot.scr".to_string()],
+        None => Vec::new(),
+    }
+}
\ No newline at end of file
@@ -0,0 +1,298 @@
+use std::fs;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::kernel::*;
+use crate::options::*;
+
+// Assinaturas das imagens do kernel: "ARM\x64" no Image do arm64 e o número
+// mágico do zImage do arm
+const ARM64_IMAGE_MAGIC: &[u8] = b"ARM\x64";
+const ARM64_IMAGE_MAGIC_OFFSET: usize = 56;
+const ZIMAGE_MAGIC: u32 = 0x016f2818;
+const ZIMAGE_MAGIC_OFFSET: usize = 0x24;
+
+// Mensagens que indicam que o boot falhou sem esperar o tempo limite
+const FAILURE_MARKERS: &[&str] = &[
+    "Kernel panic - not syncing",
+    "You are in emergency mode",
+    "Give root password for maintenance",
+];
+
+pub struct BootTestResult {
+    pub passed: bool,
+    pub reason: String,
+    pub log: String,
+}
+
+pub fn kernel_image_architecture(
+    kernel_path: &str
+) -> Result<KernelArchitecture, std::io::Error> {
+    // Identifica a arquitetura pela assinatura da imagem do kernel
+    let image = match fs::read(kernel_path) {
+        Ok(image) => image,
+        Err(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao ler o arquivo {}!", kernel_path)
+            ));
+        }
+    };
+
+    let arm64_magic = image.get(ARM64_IMAGE_MAGIC_OFFSET..ARM64_IMAGE_MAGIC_OFFSET + 4);
+    let zimage_magic = image
+        .get(ZIMAGE_MAGIC_OFFSET..ZIMAGE_MAGIC_OFFSET + 4)
+        .map(|magic| u32::from_le_bytes([magic[0], magic[1], magic[2], magic[3]]));
+
+    if arm64_magic == Some(ARM64_IMAGE_MAGIC) {
+        Ok(KernelArchitecture::Arm64)
+    } else if zimage_magic == Some(ZIMAGE_MAGIC) {
+        Ok(KernelArchitecture::Arm)
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Não foi possível identificar a arquitetura do kernel {}! Use --architecture.", kernel_path)
+        ))
+    }
+}
+
+pub fn disk_root_device(
+    disk_path: &str
+) -> Result<String, std::io::Error> {
+    // Uma imagem com tabela de partição MBR tem a raiz na primeira partição,
+    // enquanto a imagem de um sistema de arquivos é o próprio disco
+    let mut sector = [0u8; 512];
+
+    match fs::File::open(disk_path).and_then(|mut disk| disk.read_exact(&mut sector)) {
+        Ok(()) => {},
+        Err(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao ler o arquivo {}!", disk_path)
+            ));
+        }
+    }
+
+    let has_partition_table = sector[510] == 0x55 && sector[511] == 0xaa && sector[446 + 4] != 0;
+
+    Ok(if has_partition_table { "/dev/vda1" } else { "/dev/vda" }.to_string())
+}
+
+pub fn run_boot_test(
+    options: &BootTestOptions
+) -> Result<BootTestResult, std::io::Error> {
+    let architecture = match options.architecture {
+        Some(architecture) => architecture,
+        None => kernel_image_architecture(&options.kernel)?,
+    };
+
+    // Máquina virt genérica, com a console na primeira serial (PL011)
+    let (qemu, cpu) = match architecture {
+        KernelArchitecture::Arm => ("qemu-system-arm", "cortex-a15"),
+        KernelArchitecture::Arm64 => ("qemu-system-aarch64", "cortex-a53"),
+    };
+
+    let root = match &options.root {
+        Some(root) => root.clone(),
+        None => disk_root_device(&options.disk)?,
+    };
+
+    let mut append = vec![
+        format!("root={}", root),
+        "rootwait".to_string(),
+        "console=ttyAMA0".to_string(),
+        "panic=-1".to_string(),
+    ];
+    append.extend(options.extra_args.iter().cloned());
+
+    // A imagem é aberta em modo snapshot, para que o teste não a altere
+    let mut command = Command::new(qemu);
+
+    command
+        .arg("-machine")
+        .arg("virt")
+        .arg("-cpu")
+        .arg(cpu)
+        .arg("-m")
+        .arg(options.memory.to_string())
+        .arg("-nographic")
+        .arg("-no-reboot")
+        .arg("-kernel")
+        .arg(&options.kernel)
+        .arg("-drive")
+        .arg(format!("if=none,file={},format=raw,id=disk,snapshot=on", options.disk))
+        .arg("-device")
+        .arg("virtio-blk-device,drive=disk")
+        .arg("-append")
+        .arg(append.join(" "));
+
+    if let Some(initrd) = &options.initrd {
+        command.arg("-initrd").arg(initrd);
+    }
+
+    let mut child = match command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao executar o {}! Instale o pacote qemu-system-arm.", qemu)
+            ));
+        }
+    };
+
+    // Lê a console serial em uma thread, pois o prompt de login não termina
+    // com uma quebra de linha
+    let (sender, receiver) = mpsc::channel::<Vec<u8>>();
+    let mut stdout = child.stdout.take().unwrap();
+
+    thread::spawn(move || {
+        let mut buffer = [0u8; 4096];
+
+        while let Ok(size) = stdout.read(&mut buffer) {
+            if size == 0 || sender.send(buffer[..size].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+
+    // O stderr também é lido em outra thread, para que um QEMU com muitas
+    // mensagens não fique bloqueado com o pipe cheio
+    let mut stderr = child.stderr.take().unwrap();
+
+    let stderr_reader = thread::spawn(move || {
+        let mut errors: Vec<u8> = Vec::new();
+        let _ = stderr.read_to_end(&mut errors);
+        errors
+    });
+
+    let deadline = Instant::now() + Duration::from_secs(options.timeout as u64);
+    let mut console: Vec<u8> = Vec::new();
+    let mut qemu_exited = false;
+
+    let (passed, reason) = loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+
+        if remaining.is_zero() {
+            break (false, format!("O marcador \"{}\" não apareceu em {} segundos.", options.marker, options.timeout));
+        }
+
+        match receiver.recv_timeout(remaining) {
+            Ok(chunk) => console.extend(chunk),
+            Err(mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                qemu_exited = true;
+                break (false, "O QEMU terminou antes de o sistema iniciar.".to_string());
+            }
+        }
+
+        let log = String::from_utf8_lossy(&console);
+
+        if log.contains(options.marker.as_str()) {
+            break (true, format!("O marcador \"{}\" apareceu na console.", options.marker));
+        }
+
+        if let Some(marker) = FAILURE_MARKERS.iter().find(|marker| log.contains(*marker)) {
+            break (false, format!("A console indicou uma falha: \"{}\".", marker));
+        }
+    };
+
+    let _ = child.kill();
+    let _ = child.wait();
+
+    // As mensagens do QEMU explicam por que ele terminou antes do boot
+    let errors = stderr_reader.join().unwrap_or_default();
+
+    if qemu_exited {
+        console.extend(errors);
+    }
+
+    let log = String::from_utf8_lossy(&console).to_string();
+
+    if let Some(log_path) = &options.log {
+        if let Err(_) = fs::write(log_path, log.as_str()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao gravar o log da console em {}!", log_path)
+            ));
+        }
+    }
+
+    Ok(BootTestResult {
+        passed,
+        reason,
+        log,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(
+        name: &str,
+        contents: &[u8]
+    ) -> String {
+        let filepath = std::env::temp_dir().join(format!("installer-test-boot-{}-{}", name, std::process::id()));
+        fs::write(&filepath, contents).unwrap();
+        filepath.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn detects_kernel_image_architecture() {
+        let mut arm64_image = vec![0u8; 64];
+        arm64_image[ARM64_IMAGE_MAGIC_OFFSET..ARM64_IMAGE_MAGIC_OFFSET + 4].copy_from_slice(ARM64_IMAGE_MAGIC);
+
+        let mut zimage = vec![0u8; 64];
+        zimage[ZIMAGE_MAGIC_OFFSET..ZIMAGE_MAGIC_OFFSET + 4].copy_from_slice(&ZIMAGE_MAGIC.to_le_bytes());
+
+        let arm64_path = temp_file("Image", &arm64_image);
+        let zimage_path = temp_file("zImage", &zimage);
+        let unknown_path = temp_file("unknown", &[0u8; 64]);
+        let short_path = temp_file("short", &zimage[..ZIMAGE_MAGIC_OFFSET + 2]);
+
+        assert!(kernel_image_architecture(&arm64_path).unwrap() == KernelArchitecture::Arm64);
+        assert!(kernel_image_architecture(&zimage_path).unwrap() == KernelArchitecture::Arm);
+        assert!(kernel_image_architecture(&unknown_path).is_err());
+        assert!(kernel_image_architecture(&short_path).is_err());
+        assert!(kernel_image_architecture("/nonexistent/Image").is_err());
+
+        for filepath in [arm64_path, zimage_path, unknown_path, short_path] {
+            fs::remove_file(filepath).unwrap();
+        }
+    }
+
+    #[test]
+    fn detects_disk_root_device() {
+        let mut partitioned = vec![0u8; 1024];
+        partitioned[446 + 4] = 0x83;
+        partitioned[510] = 0x55;
+        partitioned[511] = 0xaa;
+
+        // Assinatura sem partições, como em alguns sistemas de arquivos
+        let mut empty_table = vec![0u8; 1024];
+        empty_table[510] = 0x55;
+        empty_table[511] = 0xaa;
+
+        let partitioned_path = temp_file("partitioned", &partitioned);
+        let empty_table_path = temp_file("empty-table", &empty_table);
+        let filesystem_path = temp_file("filesystem", &[0u8; 1024]);
+        let short_path = temp_file("short-disk", &partitioned[..511]);
+
+        assert_eq!(disk_root_device(&partitioned_path).unwrap(), "/dev/vda1");
+        assert_eq!(disk_root_device(&empty_table_path).unwrap(), "/dev/vda");
+        assert_eq!(disk_root_device(&filesystem_path).unwrap(), "/dev/vda");
+        assert!(disk_root_device(&short_path).is_err());
+
+        for filepath in [partitioned_path, empty_table_path, filesystem_path, short_path] {
+            fs::remove_file(filepath).unwrap();
+        }
+    }
+}
\ No newline at end of file
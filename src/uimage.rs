@@ -0,0 +1,482 @@
+use std::env;
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Formato de imagem legado do U-Boot (include/image.h)
+const UIMAGE_MAGIC: u32 = 0x27051956;
+const UIMAGE_NAME_SIZE: usize = 32;
+pub const UIMAGE_HEADER_SIZE: usize = 64;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ImageOs {
+    Linux,
+}
+
+impl ImageOs {
+    fn code(&self) -> u8 {
+        match self {
+            ImageOs::Linux => 5,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<ImageOs> {
+        match code {
+            5 => Some(ImageOs::Linux),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImageOs::Linux => "linux",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ImageArchitecture {
+    Arm,
+    Arm64,
+}
+
+impl ImageArchitecture {
+    fn code(&self) -> u8 {
+        match self {
+            ImageArchitecture::Arm => 2,
+            ImageArchitecture::Arm64 => 22,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<ImageArchitecture> {
+        match code {
+            2 => Some(ImageArchitecture::Arm),
+            22 => Some(ImageArchitecture::Arm64),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImageArchitecture::Arm => "arm",
+            ImageArchitecture::Arm64 => "arm64",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ImageType {
+    Kernel,
+    Ramdisk,
+    Script,
+}
+
+impl ImageType {
+    fn code(&self) -> u8 {
+        match self {
+            ImageType::Kernel => 2,
+            ImageType::Ramdisk => 3,
+            ImageType::Script => 6,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<ImageType> {
+        match code {
+            2 => Some(ImageType::Kernel),
+            3 => Some(ImageType::Ramdisk),
+            6 => Some(ImageType::Script),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImageType::Kernel => "kernel",
+            ImageType::Ramdisk => "ramdisk",
+            ImageType::Script => "script",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ImageCompression {
+    None,
+    Gzip,
+}
+
+impl ImageCompression {
+    fn code(&self) -> u8 {
+        match self {
+            ImageCompression::None => 0,
+            ImageCompression::Gzip => 1,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<ImageCompression> {
+        match code {
+            0 => Some(ImageCompression::None),
+            1 => Some(ImageCompression::Gzip),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImageCompression::None => "none",
+            ImageCompression::Gzip => "gzip",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct UImageHeader {
+    pub os: ImageOs,
+    pub architecture: ImageArchitecture,
+    pub image_type: ImageType,
+    pub compression: ImageCompression,
+    pub load_address: u32,
+    pub entry_point: u32,
+    pub timestamp: u32,
+    pub name: String,
+}
+
+pub fn uimage_timestamp() -> u32 {
+    // Assim como o mkimage, respeita SOURCE_DATE_EPOCH para gerar imagens
+    // reprodutíveis
+    if let Some(epoch) = env::var("SOURCE_DATE_EPOCH").ok().and_then(|value| value.parse().ok()) {
+        return epoch;
+    }
+
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as u32)
+        .unwrap_or(0)
+}
+
+pub fn build_uimage(
+    header: &UImageHeader,
+    data: &[u8]
+) -> Vec<u8> {
+    // Monta o cabeçalho em big-endian, com o CRC do cabeçalho zerado
+    let mut image: Vec<u8> = Vec::with_capacity(UIMAGE_HEADER_SIZE + data.len());
+
+    image.extend_from_slice(&UIMAGE_MAGIC.to_be_bytes());
+    image.extend_from_slice(&0u32.to_be_bytes());
+    image.extend_from_slice(&header.timestamp.to_be_bytes());
+    image.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    image.extend_from_slice(&header.load_address.to_be_bytes());
+    image.extend_from_slice(&header.entry_point.to_be_bytes());
+    image.extend_from_slice(&crc32(data).to_be_bytes());
+    image.push(header.os.code());
+    image.push(header.architecture.code());
+    image.push(header.image_type.code());
+    image.push(header.compression.code());
+
+    // O nome é truncado em 32 bytes e completado com zeros
+    let mut name = [0u8; UIMAGE_NAME_SIZE];
+    let length = header.name.len().min(UIMAGE_NAME_SIZE);
+    name[..length].copy_from_slice(&header.name.as_bytes()[..length]);
+    image.extend_from_slice(&name);
+
+    // Calcula o CRC do cabeçalho completo
+    let header_crc = crc32(&image);
+    image[4..8].copy_from_slice(&header_crc.to_be_bytes());
+
+    image.extend_from_slice(data);
+
+    image
+}
+
+pub fn script_image_data(script: &str) -> Vec<u8> {
+    // Scripts são imagens de múltiplos arquivos com um único componente:
+    // a tabela de tamanhos terminada em zero precede o texto do script
+    let mut data: Vec<u8> = Vec::with_capacity(script.len() + 8);
+
+    data.extend_from_slice(&(script.len() as u32).to_be_bytes());
+    data.extend_from_slice(&0u32.to_be_bytes());
+    data.extend_from_slice(script.as_bytes());
+
+    data
+}
+
+pub fn parse_uimage(
+    image: &[u8]
+) -> Result<(UImageHeader, &[u8]), std::io::Error> {
+    // Valida o número mágico e o CRC do cabeçalho
+    if image.len() < UIMAGE_HEADER_SIZE {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "A imagem é menor que o cabeçalho do U-Boot!"
+        ));
+    }
+
+    if read_u32(image, 0) != UIMAGE_MAGIC {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "A imagem não é uma imagem legada do U-Boot!"
+        ));
+    }
+
+    let mut header_bytes = image[..UIMAGE_HEADER_SIZE].to_vec();
+    header_bytes[4..8].copy_from_slice(&[0; 4]);
+
+    if crc32(&header_bytes) != read_u32(image, 4) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "O CRC do cabeçalho da imagem é inválido!"
+        ));
+    }
+
+    // Valida o tamanho e o CRC dos dados
+    let data_size = read_u32(image, 12) as usize;
+
+    if image.len() < UIMAGE_HEADER_SIZE + data_size {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "A imagem está truncada!"
+        ));
+    }
+
+    let data = &image[UIMAGE_HEADER_SIZE..UIMAGE_HEADER_SIZE + data_size];
+
+    if crc32(data) != read_u32(image, 24) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "O CRC dos dados da imagem é inválido!"
+        ));
+    }
+
+    // Interpreta os campos do cabeçalho
+    let os = ImageOs::from_code(image[28]);
+    let architecture = ImageArchitecture::from_code(image[29]);
+    let image_type = ImageType::from_code(image[30]);
+    let compression = ImageCompression::from_code(image[31]);
+
+    let (os, architecture, image_type, compression) = match (os, architecture, image_type, compression) {
+        (Some(os), Some(architecture), Some(image_type), Some(compression)) => (os, architecture, image_type, compression),
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!(
+                    "Campos desconhecidos no cabeçalho da imagem (sistema {}, arquitetura {}, tipo {}, compressão {})!",
+                    image[28], image[29], image[30], image[31]
+                )
+            ));
+        }
+    };
+
+    let name = &image[32..UIMAGE_HEADER_SIZE];
+    let name_length = name.iter().position(|&byte| byte == 0).unwrap_or(UIMAGE_NAME_SIZE);
+
+    let header = UImageHeader {
+        os,
+        architecture,
+        image_type,
+        compression,
+        load_address: read_u32(image, 16),
+        entry_point: read_u32(image, 20),
+        timestamp: read_u32(image, 8),
+        name: String::from_utf8_lossy(&name[..name_length]).to_string(),
+    };
+
+    Ok((header, data))
+}
+
+pub fn write_uimage(
+    header: &UImageHeader,
+    data_path: &str,
+    output_path: &str
+) -> Result<(), std::io::Error> {
+    // Lê os dados e grava a imagem com o cabeçalho
+    let data = match fs::read(data_path) {
+        Ok(data) => data,
+        Err(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao ler o arquivo {}!", data_path)
+            ));
+        }
+    };
+
+    if data.len() > u32::MAX as usize {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("O arquivo {} é grande demais para uma imagem do U-Boot!", data_path)
+        ));
+    }
+
+    if let Err(_) = fs::write(output_path, build_uimage(header, &data)) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao gravar o arquivo {}!", output_path)
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn read_uimage_header(
+    image_path: &str
+) -> Result<(UImageHeader, usize), std::io::Error> {
+    // Lê e valida uma imagem existente, retornando o cabeçalho e o tamanho dos dados
+    let image = match fs::read(image_path) {
+        Ok(image) => image,
+        Err(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao ler o arquivo {}!", image_path)
+            ));
+        }
+    };
+
+    let (header, data) = match parse_uimage(&image) {
+        Ok(result) => result,
+        Err(error) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("{} ({})", error, image_path)
+            ));
+        }
+    };
+
+    Ok((header, data.len()))
+}
+
+fn read_u32(
+    bytes: &[u8],
+    offset: usize
+) -> u32 {
+    u32::from_be_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    // CRC-32 (IEEE 802.3), o mesmo usado pelo U-Boot
+    let mut crc = 0xffffffffu32;
+
+    for &byte in data {
+        crc ^= byte as u32;
+
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb88320 } else { crc >> 1 };
+        }
+    }
+
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Imagens de referência no formato do mkimage, equivalentes a
+    // mkimage -A arm -O linux -T kernel -C none -a 0x600f0000 -e 0x600f0000 -n 6.1.0-rk322x e
+    // mkimage -A arm64 -O linux -T ramdisk -a 0x0 -e 0x0 -n initrd.img-6.6.0,
+    // ambas com SOURCE_DATE_EPOCH=1700000000
+    const KERNEL_IMAGE: &[u8] = &[
+        0x27, 0x05, 0x19, 0x56, 0x6e, 0x79, 0x65, 0xaa, 0x65, 0x53, 0xf1, 0x00, 0x00, 0x00, 0x00, 0x0c,
+        0x60, 0x0f, 0x00, 0x00, 0x60, 0x0f, 0x00, 0x00, 0xb5, 0x25, 0x45, 0xba, 0x05, 0x02, 0x02, 0x00,
+        0x36, 0x2e, 0x31, 0x2e, 0x30, 0x2d, 0x72, 0x6b, 0x33, 0x32, 0x32, 0x78, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x6b, 0x65, 0x72, 0x6e, 0x65, 0x6c, 0x20, 0x69, 0x6d, 0x61, 0x67, 0x65,
+    ];
+
+    const RAMDISK_IMAGE: &[u8] = &[
+        0x27, 0x05, 0x19, 0x56, 0x6b, 0x60, 0xf1, 0x13, 0x65, 0x53, 0xf1, 0x00, 0x00, 0x00, 0x00, 0x10,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xce, 0xce, 0xe2, 0x88, 0x05, 0x16, 0x03, 0x00,
+        0x69, 0x6e, 0x69, 0x74, 0x72, 0x64, 0x2e, 0x69, 0x6d, 0x67, 0x2d, 0x36, 0x2e, 0x36, 0x2e, 0x30,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+    ];
+
+    fn kernel_header() -> UImageHeader {
+        UImageHeader {
+            os: ImageOs::Linux,
+            architecture: ImageArchitecture::Arm,
+            image_type: ImageType::Kernel,
+            compression: ImageCompression::None,
+            load_address: 0x600f0000,
+            entry_point: 0x600f0000,
+            timestamp: 1700000000,
+            name: "6.1.0-rk322x".to_string(),
+        }
+    }
+
+    fn ramdisk_header() -> UImageHeader {
+        UImageHeader {
+            os: ImageOs::Linux,
+            architecture: ImageArchitecture::Arm64,
+            image_type: ImageType::Ramdisk,
+            compression: ImageCompression::None,
+            load_address: 0,
+            entry_point: 0,
+            timestamp: 1700000000,
+            name: "initrd.img-6.6.0".to_string(),
+        }
+    }
+
+    #[test]
+    fn crc32_matches_reference() {
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn builds_kernel_image() {
+        assert_eq!(build_uimage(&kernel_header(), b"kernel image"), KERNEL_IMAGE);
+    }
+
+    #[test]
+    fn builds_ramdisk_image() {
+        let data: Vec<u8> = (0..16).collect();
+        assert_eq!(build_uimage(&ramdisk_header(), &data), RAMDISK_IMAGE);
+    }
+
+    #[test]
+    fn prefixes_scripts_with_length_table() {
+        assert_eq!(
+            script_image_data("boot\n"),
+            [0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, b'b', b'o', b'o', b't', b'\n']
+        );
+    }
+
+    #[test]
+    fn truncates_long_names() {
+        let header = UImageHeader {
+            name: "x".repeat(40),
+            ..kernel_header()
+        };
+        let image = build_uimage(&header, b"");
+
+        assert_eq!(image.len(), UIMAGE_HEADER_SIZE);
+        assert_eq!(&image[32..UIMAGE_HEADER_SIZE], "x".repeat(32).as_bytes());
+        assert_eq!(parse_uimage(&image).unwrap().0.name, "x".repeat(32));
+    }
+
+    #[test]
+    fn parses_known_images() {
+        let (header, data) = parse_uimage(KERNEL_IMAGE).unwrap();
+        assert_eq!(header, kernel_header());
+        assert_eq!(data, b"kernel image");
+
+        let (header, data) = parse_uimage(RAMDISK_IMAGE).unwrap();
+        assert_eq!(header, ramdisk_header());
+        assert_eq!(data.len(), 16);
+    }
+
+    #[test]
+    fn rejects_corrupted_images() {
+        let mut image = KERNEL_IMAGE.to_vec();
+        image[0] = 0;
+        assert!(parse_uimage(&image).is_err());
+
+        let mut image = KERNEL_IMAGE.to_vec();
+        image[40] ^= 0xff;
+        assert!(parse_uimage(&image).is_err());
+
+        let mut image = KERNEL_IMAGE.to_vec();
+        image[UIMAGE_HEADER_SIZE] ^= 0xff;
+        assert!(parse_uimage(&image).is_err());
+
+        assert!(parse_uimage(&KERNEL_IMAGE[..KERNEL_IMAGE.len() - 1]).is_err());
+        assert!(parse_uimage(&KERNEL_IMAGE[..32]).is_err());
+    }
+}
\ No newline at end of file
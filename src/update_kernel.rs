@@ -0,0 +1,124 @@
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::process::Command;
+
+use crate::configure::*;
+use crate::constants::*;
+
+pub fn mount_installed_system(target: &str) -> Result<String, std::io::Error> {
+    // Cria o ponto de montagem para a partição raiz
+    if let Err(_) = fs::create_dir_all(ROOT_MOUNT_POINT) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Falha ao criar o ponto de montagem para a partição raiz!"
+        ));
+    }
+
+    // No próprio aparelho a raiz atual é montada novamente em ROOT_MOUNT_POINT,
+    // para que as mesmas rotinas da instalação possam ser usadas
+    let root_partition_path = if target == "/" {
+        let output = Command::new("findmnt")
+            .arg("--noheadings")
+            .arg("--output")
+            .arg("SOURCE")
+            .arg("/")
+            .output()?;
+
+        if !output.status.success() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "Falha ao obter a partição raiz do sistema atual!"
+            ));
+        }
+
+        let root_partition_path = resolve_root_device(String::from_utf8_lossy(&output.stdout).trim())?;
+
+        let output = Command::new("mount")
+            .arg("--bind")
+            .arg("/")
+            .arg(ROOT_MOUNT_POINT)
+            .output()?;
+
+        if !output.status.success() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "Falha ao montar a raiz do sistema atual!"
+            ));
+        }
+
+        root_partition_path
+    } else {
+        let output = Command::new("mount")
+            .arg(target)
+            .arg(ROOT_MOUNT_POINT)
+            .output()?;
+
+        if !output.status.success() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Falha ao montar a partição {}!", target)
+            ));
+        }
+
+        target.to_string()
+    };
+
+    // Verifica se a partição contém uma instalação feita por este instalador
+    let extlinux = format!("{}/boot/extlinux/extlinux.conf", ROOT_MOUNT_POINT);
+
+    if fs::metadata(extlinux).is_err() {
+        let _ = umount_root_partition();
+
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "O arquivo /boot/extlinux/extlinux.conf não foi encontrado na instalação informada!"
+        ));
+    }
+
+    Ok(root_partition_path)
+}
+
+fn resolve_root_device(source: &str) -> Result<String, std::io::Error> {
+    // O kernel informa /dev/root quando a raiz vem do root= sem initramfs.
+    // Nesse caso o dispositivo real é obtido pelo número no sysfs
+    if source != "/dev/root" && Path::new(source).exists() {
+        return Ok(source.to_string());
+    }
+
+    let device_number = fs::metadata("/").map(|metadata| metadata.dev())?;
+    let major = ((device_number >> 32) & 0xfffff000) | ((device_number >> 8) & 0x00000fff);
+    let minor = ((device_number >> 12) & 0xffffff00) | (device_number & 0x000000ff);
+    let uevent_path = format!("/sys/dev/block/{}:{}/uevent", major, minor);
+
+    let device_name = fs::read_to_string(uevent_path.as_str())
+        .ok()
+        .and_then(|uevent| {
+            uevent
+                .lines()
+                .find_map(|line| line.strip_prefix("DEVNAME=").map(|name| name.to_string()))
+        });
+
+    match device_name {
+        Some(device_name) => Ok(format!("/dev/{}", device_name)),
+        None => Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao obter o dispositivo da raiz atual ({})!", source)
+        )),
+    }
+}
+
+pub fn backup_extlinux_configuration() -> Result<(), std::io::Error> {
+    // Guarda o extlinux.conf atual antes de gerá-lo novamente
+    if let Err(_) = fs::copy(
+        format!("{}/boot/extlinux/extlinux.conf", ROOT_MOUNT_POINT),
+        format!("{}{}", ROOT_MOUNT_POINT, EXTLINUX_BACKUP)
+    ) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Falha ao copiar o arquivo /boot/extlinux/extlinux.conf para {}!", EXTLINUX_BACKUP)
+        ));
+    }
+
+    Ok(())
+}
\ No newline at end of file
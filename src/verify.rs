@@ -0,0 +1,718 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crate::fdt::*;
+use crate::kernel_config::*;
+use crate::uimage::*;
+
+// Limite de links simbólicos seguidos, como no kernel
+const MAX_SYMBOLIC_LINKS: usize = 40;
+
+pub struct BootIssue {
+    pub severity: Severity,
+    pub subject: String,
+    pub message: String,
+}
+
+#[derive(Default)]
+pub struct ExtlinuxEntry {
+    pub label: String,
+    pub kernel: Option<String>,
+    pub initrd: Option<String>,
+    pub fdt: Option<String>,
+    pub fdt_dir: Option<String>,
+    pub fdt_overlays: Vec<String>,
+    pub append: Option<String>,
+    pub localboot: bool,
+}
+
+pub struct ExtlinuxConfig {
+    pub default: Option<String>,
+    pub includes: Vec<String>,
+    pub entries: Vec<ExtlinuxEntry>,
+}
+
+pub fn parse_extlinux(
+    contents: &str
+) -> Result<ExtlinuxConfig, std::io::Error> {
+    // Interpreta as palavras-chave do extlinux.conf lidas pelo U-Boot
+    let mut config = ExtlinuxConfig {
+        default: None,
+        includes: Vec::new(),
+        entries: Vec::new(),
+    };
+
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (keyword, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let keyword = keyword.to_uppercase();
+        let value = value.trim().to_string();
+
+        match (keyword.as_str(), config.entries.last_mut()) {
+            ("LABEL", _) => config.entries.push(ExtlinuxEntry {
+                label: value,
+                ..ExtlinuxEntry::default()
+            }),
+            ("DEFAULT", _) => config.default = Some(value),
+            ("MENU" | "TIMEOUT" | "PROMPT" | "ONTIMEOUT", _) => {},
+            ("INCLUDE", _) => config.includes.push(value),
+            ("LINUX" | "KERNEL", Some(entry)) => entry.kernel = Some(value),
+            ("INITRD", Some(entry)) => entry.initrd = Some(value),
+            ("FDT" | "DEVICETREE", Some(entry)) => entry.fdt = Some(value),
+            ("FDTDIR" | "DEVICETREEDIR", Some(entry)) => entry.fdt_dir = Some(value),
+            ("LOCALBOOT", Some(entry)) => entry.localboot = true,
+            ("FDTOVERLAYS", Some(entry)) => entry.fdt_overlays = value.split_whitespace().map(|path| path.to_string()).collect(),
+            ("APPEND", Some(entry)) => entry.append = Some(value),
+            ("LINUX" | "KERNEL" | "INITRD" | "FDT" | "DEVICETREE" | "FDTDIR" | "DEVICETREEDIR" | "FDTOVERLAYS" | "APPEND" | "LOCALBOOT", None) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("A linha {} do extlinux.conf está fora de uma entrada LABEL!", number + 1)
+                ));
+            },
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("Palavra-chave desconhecida na linha {} do extlinux.conf: {}!", number + 1, keyword)
+                ));
+            }
+        }
+    }
+
+    Ok(config)
+}
+
+pub fn verify_installation(
+    target_root: &str,
+    root_partition_path: &str
+) -> Vec<BootIssue> {
+    // Confere toda a cadeia de boot da instalação montada em target_root
+    let mut issues: Vec<BootIssue> = Vec::new();
+    let mut root_devices: Vec<String> = Vec::new();
+
+    match fs::read_to_string(format!("{}/boot/extlinux/extlinux.conf", target_root)) {
+        Ok(contents) => match parse_extlinux(&contents) {
+            Ok(config) => verify_extlinux(target_root, &config, root_partition_path, &mut root_devices, &mut issues),
+            Err(error) => error_issue(&mut issues, "/boot/extlinux/extlinux.conf", &error.to_string()),
+        },
+        Err(_) => error_issue(&mut issues, "/boot/extlinux/extlinux.conf", "Falha ao ler o arquivo!"),
+    }
+
+    verify_legacy_images(target_root, &mut issues);
+    verify_fstab(target_root, root_partition_path, &root_devices, &mut issues);
+
+    issues
+}
+
+fn verify_extlinux(
+    target_root: &str,
+    config: &ExtlinuxConfig,
+    root_partition_path: &str,
+    root_devices: &mut Vec<String>,
+    issues: &mut Vec<BootIssue>
+) {
+    let subject = "/boot/extlinux/extlinux.conf";
+
+    if config.entries.is_empty() {
+        error_issue(issues, subject, "Nenhuma entrada LABEL foi encontrada!");
+        return;
+    }
+
+    match &config.default {
+        Some(default) if !config.entries.iter().any(|entry| &entry.label == default) => {
+            error_issue(issues, subject, &format!("A entrada padrão {} não existe!", default));
+        },
+        None => warning_issue(issues, subject, "Nenhuma entrada padrão (DEFAULT) foi definida."),
+        _ => {},
+    }
+
+    // Os arquivos incluídos não são verificados, apenas a sua existência
+    for include in &config.includes {
+        verify_boot_file(target_root, issues, subject, include, false);
+    }
+
+    for entry in &config.entries {
+        let subject = format!("LABEL {}", entry.label);
+
+        // Uma entrada LOCALBOOT devolve o boot ao U-Boot, sem kernel
+        if entry.localboot {
+            continue;
+        }
+
+        // O kernel pode ser uma imagem FIT, com a configuração após o #
+        match &entry.kernel {
+            Some(kernel) => match kernel.split_once('#') {
+                Some((fit_image, _)) => verify_boot_file(target_root, issues, &subject, fit_image, true),
+                None => verify_boot_file(target_root, issues, &subject, kernel, false),
+            },
+            None => error_issue(issues, &subject, "A entrada não define o kernel (LINUX)!"),
+        }
+
+        if let Some(initrd) = &entry.initrd {
+            verify_boot_file(target_root, issues, &subject, initrd, false);
+        }
+
+        if let Some(fdt) = &entry.fdt {
+            verify_boot_file(target_root, issues, &subject, fdt, true);
+        }
+
+        // Com FDTDIR, o U-Boot escolhe o DTB no diretório pelo fdtfile
+        if let Some(fdt_dir) = &entry.fdt_dir {
+            verify_boot_dir(target_root, issues, &subject, fdt_dir);
+        }
+
+        for overlay in &entry.fdt_overlays {
+            verify_boot_file(target_root, issues, &subject, overlay, true);
+        }
+
+        let append = match &entry.append {
+            Some(append) => append,
+            None => {
+                error_issue(issues, &subject, "A entrada não define os parâmetros do kernel (APPEND)!");
+                continue;
+            }
+        };
+
+        let root = append.split_whitespace().find_map(|arg| arg.strip_prefix("root="));
+        let root_filesystem = append.split_whitespace().find_map(|arg| arg.strip_prefix("rootfstype="));
+
+        match root {
+            Some(root) if !root_devices.iter().any(|device| device == root) => {
+                verify_root_device(issues, &subject, root, root_filesystem, root_partition_path);
+                root_devices.push(root.to_string());
+            },
+            Some(_) => {},
+            None => error_issue(issues, &subject, "Os parâmetros do kernel não definem a raiz (root=)!"),
+        }
+    }
+}
+
+fn verify_boot_dir(
+    target_root: &str,
+    issues: &mut Vec<BootIssue>,
+    subject: &str,
+    reference: &str
+) {
+    let path = extlinux_path(reference);
+
+    let resolved = match resolve_in_root(target_root, &path) {
+        Ok(resolved) => resolved,
+        Err(message) => {
+            error_issue(issues, subject, &format!("{}: {}", reference, message));
+            return;
+        }
+    };
+
+    if !Path::new(format!("{}{}", target_root, resolved).as_str()).is_dir() {
+        error_issue(issues, subject, &format!("{} ({}) não é um diretório!", reference, resolved));
+    }
+}
+
+fn extlinux_path(reference: &str) -> String {
+    // Os caminhos do extlinux.conf são relativos a /boot/extlinux
+    if reference.starts_with('/') {
+        reference.to_string()
+    } else {
+        format!("/boot/extlinux/{}", reference)
+    }
+}
+
+fn verify_boot_file(
+    target_root: &str,
+    issues: &mut Vec<BootIssue>,
+    subject: &str,
+    reference: &str,
+    device_tree: bool
+) {
+    let path = extlinux_path(reference);
+
+    let resolved = match resolve_in_root(target_root, &path) {
+        Ok(resolved) => resolved,
+        Err(message) => {
+            error_issue(issues, subject, &format!("{}: {}", reference, message));
+            return;
+        }
+    };
+
+    let host_path = format!("{}{}", target_root, resolved);
+
+    match fs::metadata(host_path.as_str()) {
+        Ok(metadata) if metadata.is_file() && metadata.len() > 0 => {},
+        _ => {
+            error_issue(issues, subject, &format!("{} ({}) não é um arquivo válido!", reference, resolved));
+            return;
+        }
+    }
+
+    // DTBs, overlays e imagens FIT usam o formato do device tree
+    if device_tree {
+        if let Err(error) = parse_device_tree(&fs::read(host_path.as_str()).unwrap_or_default()) {
+            error_issue(issues, subject, &format!("{} ({}): {}", reference, resolved, error));
+        }
+    }
+}
+
+pub fn resolve_in_root(
+    target_root: &str,
+    path: &str
+) -> Result<String, String> {
+    // Segue os links simbólicos dentro da instalação montada, tratando os
+    // destinos absolutos como relativos à raiz da instalação
+    let mut pending: Vec<String> = path.split('/').rev().map(|part| part.to_string()).collect();
+    let mut resolved: Vec<String> = Vec::new();
+    let mut links = 0;
+
+    while let Some(part) = pending.pop() {
+        match part.as_str() {
+            "" | "." => continue,
+            ".." => {
+                resolved.pop();
+                continue;
+            },
+            _ => {},
+        }
+
+        resolved.push(part);
+        let current = format!("/{}", resolved.join("/"));
+        let host_path = format!("{}{}", target_root, current);
+
+        let metadata = match fs::symlink_metadata(host_path.as_str()) {
+            Ok(metadata) => metadata,
+            Err(_) => return Err(format!("{} não existe!", current)),
+        };
+
+        if !metadata.file_type().is_symlink() {
+            continue;
+        }
+
+        links += 1;
+
+        if links > MAX_SYMBOLIC_LINKS {
+            return Err(format!("Links simbólicos em ciclo em {}!", current));
+        }
+
+        let target = match fs::read_link(host_path.as_str()) {
+            Ok(target) => target.to_string_lossy().to_string(),
+            Err(_) => return Err(format!("Falha ao ler o link {}!", current)),
+        };
+
+        resolved.pop();
+
+        if target.starts_with('/') {
+            resolved.clear();
+        }
+
+        pending.extend(target.split('/').rev().map(|part| part.to_string()));
+    }
+
+    Ok(format!("/{}", resolved.join("/")))
+}
+
+fn verify_legacy_images(
+    target_root: &str,
+    issues: &mut Vec<BootIssue>
+) {
+    // Confere o cabeçalho e os CRCs das imagens legadas e dos scripts de boot
+    let boot_dir = format!("{}/boot", target_root);
+    let mut images: Vec<(String, ImageType)> = Vec::new();
+
+    if let Ok(entries) = fs::read_dir(boot_dir.as_str()) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            if name.starts_with("uImage") {
+                images.push((format!("/boot/{}", name), ImageType::Kernel));
+            } else if name.starts_with("uInitrd") {
+                images.push((format!("/boot/{}", name), ImageType::Ramdisk));
+            }
+        }
+    }
+
+    images.sort_by(|first, second| first.0.cmp(&second.0));
+
+    for script in ["/boot/boot.scr", "/s905_autoscript", "/aml_autoscript"] {
+        if fs::symlink_metadata(format!("{}{}", target_root, script)).is_ok() {
+            images.push((script.to_string(), ImageType::Script));
+        }
+    }
+
+    for (image, image_type) in images {
+        let resolved = match resolve_in_root(target_root, &image) {
+            Ok(resolved) => resolved,
+            Err(message) => {
+                error_issue(issues, &image, &message);
+                continue;
+            }
+        };
+
+        match read_uimage_header(&format!("{}{}", target_root, resolved)) {
+            Ok((header, _)) if header.image_type != image_type => {
+                error_issue(issues, &image, &format!("A imagem é do tipo {}, mas deveria ser {}!", header.image_type.name(), image_type.name()));
+            },
+            Ok(_) => {},
+            Err(error) => error_issue(issues, &image, &error.to_string()),
+        }
+    }
+}
+
+fn verify_root_device(
+    issues: &mut Vec<BootIssue>,
+    subject: &str,
+    root: &str,
+    root_filesystem: Option<&str>,
+    root_partition_path: &str
+) {
+    // Fora do aparelho, como em uma máquina de CI ou em um leitor de cartões,
+    // a raiz pode ter outro nome; nesse caso é conferida a partição montada
+    let device = match find_device(root) {
+        Some(device) => device,
+        None => {
+            warning_issue(issues, subject, &format!("A raiz {} não existe nesta máquina; foi conferida a partição {}.", root, root_partition_path));
+            root_partition_path.to_string()
+        }
+    };
+
+    match filesystem_type(&device) {
+        Some(filesystem) => {
+            if let Some(root_filesystem) = root_filesystem {
+                if root_filesystem != filesystem {
+                    error_issue(issues, subject, &format!("A raiz {} é {}, mas rootfstype={}!", device, filesystem, root_filesystem));
+                }
+            }
+        },
+        None => error_issue(issues, subject, &format!("A raiz {} não contém um sistema de arquivos!", device)),
+    }
+
+    if !same_device(&device, root_partition_path) {
+        warning_issue(issues, subject, &format!("A raiz {} não é a partição verificada ({}).", root, root_partition_path));
+    }
+}
+
+fn verify_fstab(
+    target_root: &str,
+    root_partition_path: &str,
+    root_devices: &[String],
+    issues: &mut Vec<BootIssue>
+) {
+    let subject = "/etc/fstab";
+
+    let contents = match fs::read_to_string(format!("{}/etc/fstab", target_root)) {
+        Ok(contents) => contents,
+        Err(_) => {
+            error_issue(issues, subject, "Falha ao ler o arquivo!");
+            return;
+        }
+    };
+
+    let mut mount_points: Vec<String> = Vec::new();
+
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let line_subject = format!("{}, linha {}", subject, number + 1);
+
+        if fields.len() < 4 || fields.len() > 6 {
+            error_issue(issues, &line_subject, &format!("A entrada tem {} campos, mas deveria ter de 4 a 6!", fields.len()));
+            continue;
+        }
+
+        let (spec, mount_point, filesystem) = (fields[0], fields[1], fields[2]);
+
+        if !mount_point.starts_with('/') && mount_point != "none" && mount_point != "swap" {
+            error_issue(issues, &line_subject, &format!("O ponto de montagem {} é inválido!", mount_point));
+            continue;
+        }
+
+        if mount_point.starts_with('/') {
+            if mount_points.iter().any(|existing| existing == mount_point) {
+                error_issue(issues, &line_subject, &format!("O ponto de montagem {} aparece mais de uma vez!", mount_point));
+            }
+
+            mount_points.push(mount_point.to_string());
+        }
+
+        // Sistemas de arquivos virtuais, como proc e tmpfs, não têm dispositivo
+        if !spec.starts_with('/') && !spec.contains('=') {
+            continue;
+        }
+
+        // Como no extlinux.conf, a raiz ausente nesta máquina é conferida
+        // pela partição montada
+        let device = match find_device(spec) {
+            Some(device) => device,
+            None if mount_point == "/" => {
+                warning_issue(issues, &line_subject, &format!("O dispositivo {} não existe nesta máquina; foi conferida a partição {}.", spec, root_partition_path));
+                root_partition_path.to_string()
+            },
+            None => {
+                error_issue(issues, &line_subject, &format!("O dispositivo {} não existe!", spec));
+                continue;
+            }
+        };
+
+        if let Some(actual) = filesystem_type(&device) {
+            if filesystem != "auto" && filesystem != actual {
+                error_issue(issues, &line_subject, &format!("O dispositivo {} é {}, mas a entrada indica {}!", device, actual, filesystem));
+            }
+        }
+
+        // A raiz do fstab deve ser a mesma passada ao kernel
+        if mount_point == "/" {
+            for root in root_devices {
+                if let Some(root_device) = find_device(root) {
+                    if !same_device(&device, &root_device) {
+                        error_issue(issues, &line_subject, &format!("A raiz {} difere da raiz do extlinux.conf ({})!", spec, root));
+                    }
+                }
+            }
+        }
+    }
+
+    if !mount_points.iter().any(|mount_point| mount_point == "/") {
+        warning_issue(issues, subject, "Nenhuma entrada monta a raiz (/).");
+    }
+}
+
+fn find_device(
+    spec: &str
+) -> Option<String> {
+    // Aceita caminhos de dispositivo e as formas UUID=, LABEL=, PARTUUID= e PARTLABEL=
+    if spec.starts_with('/') {
+        return if Path::new(spec).exists() { Some(spec.to_string()) } else { None };
+    }
+
+    let output = Command::new("blkid")
+        .arg("--list-one")
+        .arg("--output")
+        .arg("device")
+        .arg("--match-token")
+        .arg(spec)
+        .output()
+        .ok()?;
+
+    let device = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    if output.status.success() && !device.is_empty() {
+        Some(device)
+    } else {
+        None
+    }
+}
+
+fn filesystem_type(
+    device: &str
+) -> Option<String> {
+    let output = Command::new("blkid")
+        .arg("--output")
+        .arg("value")
+        .arg("--match-tag")
+        .arg("TYPE")
+        .arg(device)
+        .output()
+        .ok()?;
+
+    let filesystem = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    if output.status.success() && !filesystem.is_empty() {
+        Some(filesystem)
+    } else {
+        None
+    }
+}
+
+fn same_device(
+    first: &str,
+    second: &str
+) -> bool {
+    match (fs::canonicalize(first), fs::canonicalize(second)) {
+        (Ok(first), Ok(second)) => first == second,
+        _ => first == second,
+    }
+}
+
+fn error_issue(
+    issues: &mut Vec<BootIssue>,
+    subject: &str,
+    message: &str
+) {
+    issues.push(BootIssue {
+        severity: Severity::Error,
+        subject: subject.to_string(),
+        message: message.to_string(),
+    });
+}
+
+fn warning_issue(
+    issues: &mut Vec<BootIssue>,
+    subject: &str,
+    message: &str
+) {
+    issues.push(BootIssue {
+        severity: Severity::Warning,
+        subject: subject.to_string(),
+        message: message.to_string(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_u_boot_menu_keywords() {
+        let contents = "\
+## /boot/extlinux/extlinux.conf gerado pelo u-boot-menu
+default l0
+menu title U-Boot menu
+prompt 0
+timeout 50
+include extlinux-extra.conf
+
+label l0
+\tmenu label Debian GNU/Linux, kernel 6.1.0-13-arm64
+\tlinux /boot/vmlinuz-6.1.0-13-arm64
+\tinitrd /boot/initrd.img-6.1.0-13-arm64
+\tfdtdir /usr/lib/linux-image-6.1.0-13-arm64/
+\tappend root=/dev/mmcblk2p1 ro quiet
+
+label l1
+\tdevicetreedir /boot/dtbs/
+\tkernel /boot/Image
+
+label local
+\tlocalboot 1
+";
+        let config = parse_extlinux(contents).unwrap();
+
+        assert_eq!(config.default.as_deref(), Some("l0"));
+        assert_eq!(config.includes, vec!["extlinux-extra.conf"]);
+        assert_eq!(config.entries.len(), 3);
+        assert_eq!(config.entries[0].fdt_dir.as_deref(), Some("/usr/lib/linux-image-6.1.0-13-arm64/"));
+        assert_eq!(config.entries[0].append.as_deref(), Some("root=/dev/mmcblk2p1 ro quiet"));
+        assert_eq!(config.entries[1].fdt_dir.as_deref(), Some("/boot/dtbs/"));
+        assert!(!config.entries[1].localboot);
+        assert!(config.entries[2].localboot);
+    }
+
+    #[test]
+    fn rejects_unknown_and_misplaced_keywords() {
+        assert!(parse_extlinux("SAY Iniciando\n").is_err());
+        assert!(parse_extlinux("FDTDIR /boot/dtb\n").is_err());
+        assert!(parse_extlinux("LOCALBOOT 1\n").is_err());
+    }
+    fn temp_root(name: &str) -> String {
+        let target_root = std::env::temp_dir().join(format!("installer-verify-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&target_root);
+        fs::create_dir_all(&target_root).unwrap();
+        target_root.to_string_lossy().to_string()
+    }
+
+    fn ext2_partition(target_root: &str) -> String {
+        // Imagem com apenas o número mágico do superbloco, que o blkid
+        // identifica como ext2
+        let mut image = vec![0; 4096];
+        image[1080..1082].copy_from_slice(&0xef53u16.to_le_bytes());
+
+        let filepath = format!("{}.img", target_root);
+        fs::write(&filepath, image).unwrap();
+        filepath
+    }
+
+    fn messages(
+        issues: &[BootIssue],
+        severity: Severity
+    ) -> Vec<&str> {
+        issues.iter().filter(|issue| issue.severity == severity).map(|issue| issue.message.as_str()).collect()
+    }
+
+    #[test]
+    fn resolves_symbolic_links_inside_root() {
+        let target_root = temp_root("resolve");
+        let boot = Path::new(&target_root).join("boot");
+        fs::create_dir_all(boot.join("dtb-6.1.0")).unwrap();
+        fs::write(boot.join("vmlinuz-6.1.0"), b"kernel").unwrap();
+        std::os::unix::fs::symlink("vmlinuz-6.1.0", boot.join("vmlinuz")).unwrap();
+        std::os::unix::fs::symlink("/boot/vmlinuz", boot.join("Image")).unwrap();
+        std::os::unix::fs::symlink("dtb-6.1.0", boot.join("dtb")).unwrap();
+        std::os::unix::fs::symlink("loop-b", boot.join("loop-a")).unwrap();
+        std::os::unix::fs::symlink("loop-a", boot.join("loop-b")).unwrap();
+
+        assert_eq!(resolve_in_root(&target_root, "/boot/vmlinuz"), Ok("/boot/vmlinuz-6.1.0".to_string()));
+        assert_eq!(resolve_in_root(&target_root, "/boot/Image"), Ok("/boot/vmlinuz-6.1.0".to_string()));
+        assert_eq!(resolve_in_root(&target_root, "/boot/dtb/../vmlinuz"), Ok("/boot/vmlinuz-6.1.0".to_string()));
+        assert_eq!(resolve_in_root(&target_root, "/boot/extlinux/../dtb"), Err("/boot/extlinux não existe!".to_string()));
+        assert_eq!(resolve_in_root(&target_root, "/boot/uImage"), Err("/boot/uImage não existe!".to_string()));
+        assert!(resolve_in_root(&target_root, "/boot/loop-a").unwrap_err().contains("em ciclo"));
+
+        fs::remove_dir_all(&target_root).unwrap();
+    }
+
+    #[test]
+    fn checks_mounted_partition_when_root_is_absent() {
+        let target_root = temp_root("root-device");
+        let partition = ext2_partition(&target_root);
+        let root = "/dev/installer-ausente1";
+
+        let mut issues: Vec<BootIssue> = Vec::new();
+        verify_root_device(&mut issues, "LABEL l0", root, Some("ext2"), &partition);
+
+        assert!(messages(&issues, Severity::Error).is_empty());
+        assert_eq!(messages(&issues, Severity::Warning).len(), 1);
+
+        let mut issues: Vec<BootIssue> = Vec::new();
+        verify_root_device(&mut issues, "LABEL l0", root, Some("ext4"), &partition);
+
+        assert_eq!(messages(&issues, Severity::Error), vec![format!("A raiz {} é ext2, mas rootfstype=ext4!", partition)]);
+
+        fs::remove_dir_all(&target_root).unwrap();
+        fs::remove_file(&partition).unwrap();
+    }
+
+    #[test]
+    fn checks_mounted_partition_for_absent_fstab_root() {
+        let target_root = temp_root("fstab");
+        let partition = ext2_partition(&target_root);
+        let root_devices = vec!["/dev/installer-ausente1".to_string()];
+        fs::create_dir_all(Path::new(&target_root).join("etc")).unwrap();
+
+        fs::write(
+            Path::new(&target_root).join("etc/fstab"),
+            "/dev/installer-ausente1\t/\text2\tdefaults\t0\t1\nproc\t/proc\tproc\tdefaults\t0\t0\n"
+        ).unwrap();
+
+        let mut issues: Vec<BootIssue> = Vec::new();
+        verify_fstab(&target_root, &partition, &root_devices, &mut issues);
+
+        assert!(messages(&issues, Severity::Error).is_empty());
+        assert_eq!(messages(&issues, Severity::Warning).len(), 1);
+
+        fs::write(
+            Path::new(&target_root).join("etc/fstab"),
+            "/dev/installer-ausente1\t/\text4\tdefaults\t0\t1\n/dev/installer-ausente2\t/home\text4\tdefaults\t0\t2\n"
+        ).unwrap();
+
+        let mut issues: Vec<BootIssue> = Vec::new();
+        verify_fstab(&target_root, &partition, &root_devices, &mut issues);
+
+        assert_eq!(
+            messages(&issues, Severity::Error),
+            vec![
+                format!("O dispositivo {} é ext2, mas a entrada indica ext4!", partition),
+                "O dispositivo /dev/installer-ausente2 não existe!".to_string(),
+            ]
+        );
+
+        fs::remove_dir_all(&target_root).unwrap();
+        fs::remove_file(&partition).unwrap();
+    }
+}
\ No newline at end of file